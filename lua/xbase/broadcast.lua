@@ -9,10 +9,81 @@ local M = {}
 
 M.expect_second_run = false
 
+---@type string[] @Message kinds handled by this client
+M.supported_messages = {
+  "Notify",
+  "Log",
+  "OpenLogger",
+  "ReloadLspServer",
+  "SetCurrentTask",
+  "UpdateCurrentTask",
+  "TaskProgress",
+  "Diagnostic",
+  "TestSuite",
+  "TestCase",
+  "FinishCurrentTask",
+  "SetWatching",
+  "SetState",
+}
+
+---@type table<number, table> @Tasks under progress indexed by task id
+M.tasks = {}
+
+---@type table<string, string> @Quickfix item types indexed by diagnostic severity
+local qf_types = { Error = "E", Warning = "W", Note = "N" }
+
+--- Create a new quickfix list for diagnostics of a build
+local function diagnostics_reset()
+  vim.fn.setqflist({}, " ", { title = "xbase", items = {} })
+  M.qf_id = vim.fn.getqflist({ id = 0 }).id
+end
+
+--- Add a compiler diagnostic and its notes to the build quickfix list
+local function diagnostic_add(diagnostic)
+  local items = {
+    {
+      filename = diagnostic.file,
+      lnum = diagnostic.line,
+      col = diagnostic.column,
+      type = qf_types[diagnostic.severity],
+      text = diagnostic.message,
+    },
+  }
+  for _, note in ipairs(diagnostic.notes) do
+    table.insert(items, {
+      filename = note.file,
+      lnum = note.line,
+      col = note.column,
+      type = "N",
+      text = note.message,
+    })
+  end
+
+  if not M.qf_id or vim.fn.getqflist({ id = M.qf_id }).id == 0 then
+    diagnostics_reset()
+  end
+  vim.fn.setqflist({}, "a", { id = M.qf_id, items = items })
+end
+
+--- Fill the quickfix list with diagnostics of builds that finished before registering
+---@param targets table<string, table[]> @diagnostics indexed by target
+function M.load_diagnostics(targets)
+  diagnostics_reset()
+  for _, diagnostics in pairs(targets) do
+    for _, diagnostic in ipairs(diagnostics) do
+      diagnostic_add(diagnostic)
+    end
+  end
+end
+
 local function task_set(args)
   M.has_task = true
+  if tkind.is_build(args.kind) then
+    diagnostics_reset()
+  end
   local running, _ = tkind:prefix(args.kind)
   args.prefix = running
+  M.tasks[args.id] = args
   vim.g.xbase_ctask = args
   local line = string.format("[%s] %s", args.target, args.prefix)
   if tkind.is_run(args.kind) then
@@ -28,12 +99,20 @@ local function task_update(args)
   if #content == 0 then
     return
   end
-  local ctask = vim.g.xbase_ctask
+  logger.log(content, level)
+
+  local ctask = M.tasks[args.id]
+  if not ctask then
+    return
+  end
   local target, prefix, kind = ctask.target, ctask.prefix, ctask.kind
 
-  logger.log(content, level)
+  -- Show the task in the statusline unless another task under progress is already shown
+  if not vim.g.xbase_ctask or not M.tasks[vim.g.xbase_ctask.id] then
+    vim.g.xbase_ctask = ctask
+  end
 
-  if level ~= "Debug" and level ~= "Trace" then
+  if vim.g.xbase_ctask.id == args.id and level ~= "Debug" and level ~= "Trace" then
     content = string.gsub(content, "%[" .. target .. "%]%s", "")
     local line = string.format("[%s] %s: %s", target, prefix, content)
 
@@ -47,11 +126,54 @@ local function task_update(args)
   end
 end
 
+local function task_progress(args)
+  local ctask = M.tasks[args.id]
+  if not ctask or args.total == 0 then
+    return
+  end
+  ctask.progress = args.completed / args.total
+
+  if vim.g.xbase_ctask and vim.g.xbase_ctask.id == args.id then
+    vim.g.xbase_ctask = vim.tbl_extend("force", vim.g.xbase_ctask, { progress = ctask.progress })
+  end
+end
+
+local function test_suite(suite)
+  if suite.status == "Started" then
+    return
+  end
+  local level = suite.status == "Failed" and "Error" or "Info"
+  local line = ("[%s] %s: %d tests, %d failures"):format(suite.name, suite.status, suite.tests, suite.failures)
+  logger.log(line, level)
+end
+
+local function test_case(case)
+  if case.status == "Started" then
+    return
+  end
+  local level = case.status == "Failed" and "Error" or "Info"
+  local duration = case.duration ~= vim.NIL and case.duration or 0
+  logger.log(("  %s %s.%s (%dms)"):format(case.status, case.suite, case.name, duration), level)
+  for _, failure in ipairs(case.failures) do
+    logger.log(("    %s:%d: %s"):format(failure.file, failure.line, failure.message), "Error")
+  end
+end
+
 local function task_finish(args)
-  M.has_task = false
-  vim.g.xbase_ctask = vim.tbl_extend("force", vim.g.xbase_ctask, args)
+  local ctask = M.tasks[args.id]
+  if not ctask then
+    return
+  end
+  M.tasks[args.id] = nil
+  M.has_task = next(M.tasks) ~= nil
+  ctask = vim.tbl_extend("force", ctask, args)
+
+  local shown = not vim.g.xbase_ctask or vim.g.xbase_ctask.id == args.id
+  if shown then
+    vim.g.xbase_ctask = ctask
+  end
+
   local line, level, icon
-  local ctask = vim.g.xbase_ctask
   local target, prefix = ctask.target, ctask.prefix
   local is_failed = tstatus.is_failed(args.status)
 
@@ -63,8 +185,10 @@ local function task_finish(args)
     level = "Info"
   end
 
-  if tkind.is_run(vim.g.xbase_ctask.kind) then
-    vim.g.xbase_ctask_display = ("%s [%s] Device Disconnected"):format(icon, target)
+  if tkind.is_run(ctask.kind) then
+    if shown then
+      vim.g.xbase_ctask_display = ("%s [%s] Device Disconnected"):format(icon, target)
+    end
   else
     if is_failed then
       line = ("[%s] %s Failed"):format(target, prefix)
@@ -72,7 +196,9 @@ local function task_finish(args)
       local _, done = tkind:prefix(ctask.kind)
       line = ("[%s] %s"):format(target, done)
     end
-    vim.g.xbase_ctask_line = ("%s %s"):format(icon, line)
+    if shown then
+      vim.g.xbase_ctask_line = ("%s %s"):format(icon, line)
+    end
   end
 
   vim.schedule(function()
@@ -88,10 +214,17 @@ local function task_finish(args)
   end)
 end
 
-function M.start(root, address)
+---@type string[] @ContentLevel names indexed by vim.log.levels
+local content_levels = { [0] = "Trace", "Debug", "Info", "Warn", "Error" }
+
+function M.start(root, address, id, token)
   local socket = socket:connect(address)
+  local subscription = vim.json.encode {
+    level = content_levels[config.log_level] or vim.NIL,
+    messages = M.supported_messages,
+  }
 
-  socket._socket:write(string.format("%s\n", vim.loop.os_getpid()), function(err)
+  socket._socket:write(string.format("%s %s\n%s\n", id, token, subscription), function(err)
     if err then
       print(socket._stream_error or err)
     end
@@ -121,6 +254,22 @@ function M.start(root, address)
           return task_set(args)
         end
 
+        if msg.task_is_progress(type) then
+          return task_progress(args)
+        end
+
+        if msg.is_diagnostic(type) then
+          return diagnostic_add(args.diagnostic)
+        end
+
+        if msg.is_test_suite(type) then
+          return test_suite(args.suite)
+        end
+
+        if msg.is_test_case(type) then
+          return test_case(args.case)
+        end
+
         if msg.task_is_finish_current(type) then
           return task_finish(args)
         end
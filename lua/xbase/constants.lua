@@ -1,8 +1,64 @@
 local M = {}
 
 -- AUTOGENERATED
-M.SOCK_ADDR = '/tmp/xbase.socket'
+M.CONFIG_PATH_ENV = 'XBASE_CONFIG'
+M.CONFIG_FILE = 'xbase/daemon.json'
+M.RUNTIME_DIR_ENV = 'XBASE_RUNTIME_DIR'
+M.SOCK_ADDR_ENV = 'XBASE_SOCK_ADDR'
+M.LOG_PATH_ENV = 'XBASE_LOG_PATH'
+M.SOCK_NAME = 'xbase.socket'
+M.LOG_NAME = 'xbase.log'
+M.PROTOCOL_VERSION = 2
+M.ERROR_CODES = {
+  Unexpected = 1,
+  Setup = 2,
+  Lookup = 3,
+  Build = 4,
+  Run = 5,
+  Generate = 6,
+  Compile = 7,
+  DefinitionParsing = 8,
+  DefinitionLocating = 9,
+  DefinitionMutliFound = 10,
+  JoinError = 11,
+  SendError = 12,
+  MessageParse = 13,
+  RequestParse = 14,
+  Incompatible = 15,
+  Unauthorized = 16,
+  UnknownProject = 17,
+}
 M.BIN_ROOT = string.gsub('$HOME/.local/share/xbase', '$HOME', vim.env.HOME)
 
+local function env(key)
+  local value = vim.env[key]
+  if value ~= nil and #value > 0 then
+    return value
+  end
+end
+
+local function config_file()
+  local home = env "XDG_CONFIG_HOME" or (vim.env.HOME .. "/.config")
+  local path = env(M.CONFIG_PATH_ENV) or (home .. "/" .. M.CONFIG_FILE)
+  local fd = io.open(path, "r")
+  if fd == nil then
+    return {}
+  end
+  local content = fd:read "*a"
+  fd:close()
+  local ok, value = pcall(vim.json.decode, content)
+  return ok and value or {}
+end
+
+local config = config_file()
+local xdg_runtime_dir = env "XDG_RUNTIME_DIR"
+
+M.RUNTIME_DIR = env(M.RUNTIME_DIR_ENV)
+  or config.runtime_dir
+  or (xdg_runtime_dir and (xdg_runtime_dir .. "/xbase"))
+  or (vim.loop.os_tmpdir() .. "/xbase-" .. vim.loop.getuid())
+M.SOCK_ADDR = env(M.SOCK_ADDR_ENV) or config.sock_addr or (M.RUNTIME_DIR .. "/" .. M.SOCK_NAME)
+M.LOG_PATH = env(M.LOG_PATH_ENV) or config.log_path or (M.RUNTIME_DIR .. "/" .. M.LOG_NAME)
+
 
 return M
\ No newline at end of file
@@ -5,7 +5,6 @@ local notify = require "xbase.notify"
 local broadcast = require "xbase.broadcast"
 local constants = require "xbase.constants"
 local uv = vim.loop
-local id = vim.loop.os_getpid()
 
 ---@class XBase
 local M = {
@@ -13,6 +12,10 @@ local M = {
   socket = nil,
   ---@type string[] @list of registered roots
   roots = {},
+  ---@type string? @session token used to resume the session after reconnecting
+  session = nil,
+  ---@type boolean @whether responses are being read from the socket
+  reading = false,
 }
 
 ---Spawn xbase daemon in detached mode and executes cb on first stdout
@@ -44,26 +47,76 @@ function M.ensure_connection(cb)
   cb()
 end
 
+---@type table<number, function> @Response handlers of requests waiting for a response indexed by id
+local pending = {}
+---@type number @Id of the next request
+local next_id = 0
+---@type string @Received content not terminated by a newline yet
+local buffer = ""
+
+---Forget the daemon socket and requests waiting for a response, so that the next request reconnects
+local function disconnect()
+  M.socket:close()
+  M.socket = nil
+  M.reading = false
+  pending = {}
+  buffer = ""
+end
+
+---Process newline delimited responses and schedule the handler of the request each is for, as
+---luv calls this in a fast event context where most of the nvim api can't be used
+---@param err string|nil
+---@param chunk string|nil @nil once the daemon closed the socket
+local function on_read(err, chunk)
+  if err or chunk == nil then
+    disconnect()
+    if err then
+      vim.schedule(function()
+        notify.error(string.format("Failed to read xbase daemon responses: %s", err))
+      end)
+    end
+    return
+  end
+
+  buffer = buffer .. chunk
+  local idx = buffer:find("\n", 1, true)
+  while idx do
+    local line = buffer:sub(1, idx - 1)
+    buffer = buffer:sub(idx + 1)
+    if #vim.trim(line) ~= 0 then
+      local res = vim.json.decode(line)
+      local handler = res.id ~= nil and res.id ~= vim.NIL and pending[res.id] or nil
+      if handler then
+        pending[res.id] = nil
+        vim.schedule(function()
+          handler(res)
+        end)
+      end
+    end
+    idx = buffer:find("\n", 1, true)
+  end
+end
+
 ---Send Request to socket, and on response call on_response with data if no error
 ---@param req table
 ---@param on_response? function(response:table)
 function M.request(req, on_response)
   M.ensure_connection(function()
-    M.socket:read_start(function(chunk)
-      vim.schedule(function()
-        local res = vim.json.decode(chunk)
-        if res.error then
-          notify.error(string.format("%s %s", res.error.kind, res.error.msg))
-          return
-        else
-          if on_response then
-            on_response(res.data)
-          end
-        end
-      end)
-      M.socket:read_stop()
-    end)
-    M.socket:write(req)
+    if not M.reading then
+      M.socket._socket:read_start(on_read)
+      M.reading = true
+    end
+
+    local id = next_id
+    next_id = next_id + 1
+    pending[id] = function(res)
+      if res.error ~= nil and res.error ~= vim.NIL then
+        notify.error(string.format("%s %s", res.error.kind, res.error.msg))
+      elseif on_response then
+        on_response(res.data)
+      end
+    end
+    M.socket:write(vim.tbl_extend("force", req, { id = id }))
   end)
 end
 
@@ -94,13 +147,40 @@ function M.register(root)
 
   require("xbase.logger").setup()
 
-  local req = { method = "register", args = { id = id, root = root } }
-  M.request(req, function(broadcast_address)
-    broadcast.start(root, broadcast_address)
+  local client = {
+    name = "nvim",
+    protocol = constants.PROTOCOL_VERSION,
+    messages = broadcast.supported_messages,
+  }
+  local req = { method = "register", args = { root = root, client = client, session = M.session } }
+  M.request(req, function(res)
+    M.session = res.session
+    broadcast.start(root, res.address, res.id, res.broadcast_token)
     M.roots[root] = true
+    M.get_diagnostics(root, nil, function(targets)
+      if not vim.tbl_isempty(targets) then
+        broadcast.load_diagnostics(targets)
+      end
+    end)
   end)
 end
 
+---Get diagnostics of the last build of each target, optionally only about a given file
+---@param root string
+---@param file string|nil
+---@param on_response fun(targets: table<string, table[]>)
+function M.get_diagnostics(root, file, on_response)
+  M.request({ method = "get_diagnostics", args = { root = root, file = file } }, on_response)
+end
+
+---Build and run tests of a test target, results are broadcast as TestSuite and TestCase messages
+---@param root string
+---@param settings XBaseSettings @settings with the test target
+---@param filter string|nil @only run tests matching `Class` or `Class/testName`
+function M.test(root, settings, filter)
+  M.request { method = "test", args = { root = root, settings = settings, filter = filter } }
+end
+
 ---Drop a given root or drop all tracked roots if root is nil
 ---@param root string
 function M.drop(root)
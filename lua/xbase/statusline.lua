@@ -20,6 +20,15 @@ function M.should_spin()
   return tstatus.is_processing(vim.g.xbase_ctask.status) and (not tkind.is_run(vim.g.xbase_ctask.kind))
 end
 
+---@param progress number @completed ratio, from 0 to 1
+---@return string @progress bar, e.g. [███░░░░░░░] 30%
+function M.progress_bar(progress)
+  local width = 10
+  local filled = math.floor(progress * width)
+  local bar = string.rep("█", filled) .. string.rep("░", width - filled)
+  return ("[%s] %d%%"):format(bar, math.floor(progress * 100))
+end
+
 function M.update_spinner()
   if M.should_spin() then
     M.spinner_idx = M.spinner_idx + 1
@@ -27,7 +36,9 @@ function M.update_spinner()
       M.spinner_idx = 1
     end
 
-    vim.g.xbase_ctask_display = ("%s %s"):format(M.spinner[M.spinner_idx], vim.g.xbase_ctask_line)
+    local progress = vim.g.xbase_ctask.progress
+    local icon = progress and M.progress_bar(progress) or M.spinner[M.spinner_idx]
+    vim.g.xbase_ctask_display = ("%s %s"):format(icon, vim.g.xbase_ctask_line)
 
     vim.defer_fn(function()
       if M.should_spin() then
@@ -18,6 +18,9 @@ M.TaskKind = {
   is_run = function(kind)
     return kind == "Run"
   end,
+  is_test = function(kind)
+    return kind == "Test"
+  end,
   prefix = function(self, kind)
     if self.is_compile(kind) then
       return "Compiling", "Compiled"
@@ -27,13 +30,15 @@ M.TaskKind = {
       return "Building", "Built"
     elseif self.is_run(kind) then
       return "Running", "Running"
+    elseif self.is_test(kind) then
+      return "Testing", "Tested"
     end
   end,
 }
 
 M.TaskStatus = {
   is_failed = function(status)
-    return status == "Failed"
+    return status == "Failed" or status == "Canceled"
   end,
   is_succeeded = function(status)
     return status == "Succeeded"
@@ -62,6 +67,18 @@ M.Message = {
   task_is_set_current = function(ty)
     return ty == "SetCurrentTask"
   end,
+  task_is_progress = function(ty)
+    return ty == "TaskProgress"
+  end,
+  is_diagnostic = function(ty)
+    return ty == "Diagnostic"
+  end,
+  is_test_suite = function(ty)
+    return ty == "TestSuite"
+  end,
+  is_test_case = function(ty)
+    return ty == "TestCase"
+  end,
   task_is_finish_current = function(ty)
     return ty == "FinishCurrentTask"
   end,
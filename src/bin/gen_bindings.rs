@@ -30,18 +30,52 @@ fn gen_ts_types_file(path: PathBuf) {
     options.root_namespace = None;
     options.header = None;
 
-    type Requests = (Request, RunRequest, RegisterRequest, DropRequest);
-    type Responses = (Response, ServerError);
+    type Requests = (
+        Request,
+        RunRequest,
+        RegisterRequest,
+        DropRequest,
+        StatusRequest,
+        ShutdownRequest,
+        AttachRequest,
+        TasksRequest,
+        GetDiagnosticsRequest,
+        TestRequest,
+        ClientInfo,
+    );
+    type Responses = (
+        Response,
+        ServerError,
+        Error,
+        RuntimeStatus,
+        RuntimeInfo,
+        RegisterResponse,
+        DaemonInfo,
+    );
     type Transports = (
         ProjectInfo,
         TargetInfo,
+        TestCaseInfo,
         Runners,
         Operation,
         BuildSettings,
         DeviceLookup,
         State,
+        ProjectKind,
+    );
+    type Messages = (
+        Message,
+        ContentLevel,
+        TaskKind,
+        TaskStatus,
+        TaskInfo,
+        Diagnostic,
+        DiagnosticSeverity,
+        TestCase,
+        TestSuite,
+        TestFailure,
+        TestStatus,
     );
-    type Messages = (Message, ContentLevel, TaskKind, TaskStatus);
     type API = (Messages, Transports, Responses, Requests);
 
     write_definition_file::<_, API>(&mut buf, options).unwrap();
@@ -53,7 +87,7 @@ fn gen_ts_types_file(path: PathBuf) {
 
 #[cfg(feature = "gen")]
 fn gen_ts_constant(path: PathBuf) {
-    use xbase::*;
+    use xbase::{config::*, server::PROTOCOL_VERSION, *};
     let mut output = read_file_content(&path);
 
     macro_rules! export {
@@ -62,18 +96,60 @@ fn gen_ts_constant(path: PathBuf) {
         };
     }
 
-    export!(SOCK_ADDR);
+    export!(CONFIG_PATH_ENV);
+    export!(CONFIG_FILE);
+    export!(RUNTIME_DIR_ENV);
+    export!(SOCK_ADDR_ENV);
+    export!(LOG_PATH_ENV);
+    export!(SOCK_NAME);
+    export!(LOG_NAME);
+    output += &format!("export const XBASE_PROTOCOL_VERSION = {PROTOCOL_VERSION}\n");
+    output += "export const XBASE_ERROR_CODES = {\n";
+    for (kind, code) in ERROR_CODES {
+        output += &format!("  {kind}: {code},\n");
+    }
+    output += "} as const\n";
 
     output += &format!(
         "export const XBASE_BIN_ROOT = '{BIN_ROOT}'.replace('$HOME', process.env.HOME!)\n"
     );
 
+    // Mirror of xbase::config::Config::resolve
+    output += r#"
+import * as fs from "fs";
+import * as os from "os";
+
+const env = (key: string) => {
+  const value = process.env[key];
+  return value !== undefined && value.length > 0 ? value : undefined;
+};
+
+const configFile = (): Record<string, string | undefined> => {
+  const home = env("XDG_CONFIG_HOME") ?? `${process.env.HOME}/.config`;
+  const path = env(XBASE_CONFIG_PATH_ENV) ?? `${home}/${XBASE_CONFIG_FILE}`;
+  try {
+    return JSON.parse(fs.readFileSync(path, "utf8"));
+  } catch {
+    return {};
+  }
+};
+
+const config = configFile();
+const xdgRuntimeDir = env("XDG_RUNTIME_DIR");
+
+export const XBASE_RUNTIME_DIR = env(XBASE_RUNTIME_DIR_ENV)
+  ?? config.runtime_dir
+  ?? (xdgRuntimeDir !== undefined ? `${xdgRuntimeDir}/xbase` : `${os.tmpdir()}/xbase-${process.getuid?.()}`);
+export const XBASE_SOCK_ADDR = env(XBASE_SOCK_ADDR_ENV) ?? config.sock_addr ?? `${XBASE_RUNTIME_DIR}/${XBASE_SOCK_NAME}`;
+export const XBASE_LOG_PATH = env(XBASE_LOG_PATH_ENV) ?? config.log_path ?? `${XBASE_RUNTIME_DIR}/${XBASE_LOG_NAME}`;
+"#;
+
     std::fs::write(&path, output).expect("failed to write typescript types");
 }
 
 #[cfg(feature = "gen")]
 fn gen_lua_constant(path: PathBuf) {
-    use xbase::*;
+    use xbase::{config::*, server::PROTOCOL_VERSION, *};
     let mut output = read_file_content(&path);
 
     macro_rules! export {
@@ -82,8 +158,53 @@ fn gen_lua_constant(path: PathBuf) {
         };
     }
 
-    export!(SOCK_ADDR);
+    export!(CONFIG_PATH_ENV);
+    export!(CONFIG_FILE);
+    export!(RUNTIME_DIR_ENV);
+    export!(SOCK_ADDR_ENV);
+    export!(LOG_PATH_ENV);
+    export!(SOCK_NAME);
+    export!(LOG_NAME);
+    output += &format!("M.PROTOCOL_VERSION = {PROTOCOL_VERSION}\n");
+    output += "M.ERROR_CODES = {\n";
+    for (kind, code) in ERROR_CODES {
+        output += &format!("  {kind} = {code},\n");
+    }
+    output += "}\n";
     output += &format!("M.BIN_ROOT = string.gsub('{BIN_ROOT}', '$HOME', vim.env.HOME)\n",);
+
+    // Mirror of xbase::config::Config::resolve
+    output += r#"
+local function env(key)
+  local value = vim.env[key]
+  if value ~= nil and #value > 0 then
+    return value
+  end
+end
+
+local function config_file()
+  local home = env "XDG_CONFIG_HOME" or (vim.env.HOME .. "/.config")
+  local path = env(M.CONFIG_PATH_ENV) or (home .. "/" .. M.CONFIG_FILE)
+  local fd = io.open(path, "r")
+  if fd == nil then
+    return {}
+  end
+  local content = fd:read "*a"
+  fd:close()
+  local ok, value = pcall(vim.json.decode, content)
+  return ok and value or {}
+end
+
+local config = config_file()
+local xdg_runtime_dir = env "XDG_RUNTIME_DIR"
+
+M.RUNTIME_DIR = env(M.RUNTIME_DIR_ENV)
+  or config.runtime_dir
+  or (xdg_runtime_dir and (xdg_runtime_dir .. "/xbase"))
+  or (vim.loop.os_tmpdir() .. "/xbase-" .. vim.loop.getuid())
+M.SOCK_ADDR = env(M.SOCK_ADDR_ENV) or config.sock_addr or (M.RUNTIME_DIR .. "/" .. M.SOCK_NAME)
+M.LOG_PATH = env(M.LOG_PATH_ENV) or config.log_path or (M.RUNTIME_DIR .. "/" .. M.LOG_NAME)
+"#;
     output += "\n\nreturn M";
 
     std::fs::write(&path, output).expect("failed to write typescript types");
@@ -0,0 +1,365 @@
+use super::{ContentLevel, Message, TaskKind, TaskStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use typescript_type_def::TypeDef;
+
+/// Compiler diagnostic parsed from build output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct Diagnostic {
+    /// Source file the diagnostic is about
+    pub file: PathBuf,
+    /// Line number, starting at 1
+    pub line: u32,
+    /// Column number, starting at 1, 0 if unknown
+    pub column: u32,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    /// Notes following the diagnostic, e.g. where a conflicting symbol is declared
+    pub notes: Vec<DiagnosticNote>,
+}
+
+/// Note attached to a compiler diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct DiagnosticNote {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+}
+
+/// Severity of a compiler diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TypeDef)]
+pub enum DiagnosticSeverity {
+    Note,
+    Warning,
+    Error,
+}
+
+/// Diagnostics of the last build of each target, replaced as builds finish so that files that
+/// compile cleanly on rebuild no longer have entries.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsStore(Arc<Mutex<DiagnosticsState>>);
+
+#[derive(Debug, Default)]
+struct DiagnosticsState {
+    /// Diagnostics of the last finished build indexed by target
+    targets: HashMap<String, Vec<Diagnostic>>,
+    /// Target and diagnostics of builds under progress indexed by task id
+    pending: HashMap<u32, (String, Vec<Diagnostic>)>,
+}
+
+/// Collect diagnostics from output lines, attaching notes to the diagnostic they follow and
+/// skipping diagnostics already reported (xcodebuild often prints them twice).
+#[derive(Debug, Default)]
+pub(crate) struct DiagnosticCollector {
+    pending: Option<Diagnostic>,
+    reported: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// Parse a `file:line:column: severity: message` line, as printed by swiftc, clang, swift
+    /// build and xcodebuild. Lines prefixed by XCLogger with `[Error]` or `[Warning]` are accepted
+    /// without a severity.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (prefix, line) = match line.strip_prefix('[').and_then(|l| l.split_once("] ")) {
+            Some((prefix, rest)) => (DiagnosticSeverity::from_label(prefix), rest),
+            None => (None, line),
+        };
+
+        let (file, line_number, column, rest) = line
+            .match_indices(": ")
+            .find_map(|(idx, _)| Some((parse_location(&line[..idx])?, &line[idx + 2..])))
+            .map(|((file, line, column), rest)| (file, line, column, rest))?;
+
+        let (severity, message) = match rest.split_once(": ") {
+            Some((label, message)) => match DiagnosticSeverity::from_label(label) {
+                Some(severity) => (severity, message),
+                None => (prefix?, rest),
+            },
+            None => (prefix?, rest),
+        };
+
+        Some(Self {
+            file: file.into(),
+            line: line_number,
+            column,
+            severity,
+            message: message.trim().into(),
+            notes: vec![],
+        })
+    }
+}
+
+/// Parse `file:line:column` or `file:line`
+fn parse_location(location: &str) -> Option<(&str, u32, u32)> {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?.parse::<u32>().ok()?;
+    let second = parts.next()?;
+    match (second.parse::<u32>().ok(), parts.next()) {
+        (Some(line), Some(file)) if !file.is_empty() => Some((file, line, last)),
+        (None, None) if !second.is_empty() && !second.contains(' ') => Some((second, last, 0)),
+        _ => None,
+    }
+}
+
+impl DiagnosticSeverity {
+    fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "error" | "fatal error" => Some(Self::Error),
+            "warning" | "warn" => Some(Self::Warning),
+            "note" | "remark" => Some(Self::Note),
+            _ => None,
+        }
+    }
+
+    /// Level output lines of this severity are logged with
+    pub fn level(&self) -> ContentLevel {
+        match self {
+            Self::Error => ContentLevel::Error,
+            Self::Warning => ContentLevel::Warn,
+            Self::Note => ContentLevel::Info,
+        }
+    }
+}
+
+impl DiagnosticCollector {
+    /// Add a parsed diagnostic, returns the previous one once it's complete
+    pub fn push(&mut self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if let (DiagnosticSeverity::Note, Some(pending)) =
+            (diagnostic.severity, self.pending.as_mut())
+        {
+            pending.notes.push(DiagnosticNote {
+                file: diagnostic.file,
+                line: diagnostic.line,
+                column: diagnostic.column,
+                message: diagnostic.message,
+            });
+            return None;
+        }
+
+        self.pending
+            .replace(diagnostic)
+            .and_then(|d| self.report(d))
+    }
+
+    /// Return the last diagnostic, once output ended
+    pub fn finish(&mut self) -> Option<Diagnostic> {
+        self.pending.take().and_then(|d| self.report(d))
+    }
+
+    /// Diagnostics reported so far
+    pub fn reported(&self) -> &[Diagnostic] {
+        &self.reported
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        if self.reported.contains(&diagnostic) {
+            return None;
+        }
+        self.reported.push(diagnostic.clone());
+        Some(diagnostic)
+    }
+}
+
+impl DiagnosticsStore {
+    /// Track diagnostics of build and compile tasks from a broadcast message
+    pub(crate) fn record(&self, message: &Message) {
+        let mut state = self.0.lock().unwrap();
+        match message {
+            Message::SetCurrentTask {
+                id, kind, target, ..
+            } if matches!(kind, TaskKind::Build | TaskKind::Compile) => {
+                state.pending.insert(*id, (target.clone(), vec![]));
+            }
+            Message::Diagnostic { id, diagnostic, .. } => {
+                if let Some((_, diagnostics)) = state.pending.get_mut(id) {
+                    diagnostics.push(diagnostic.clone());
+                }
+            }
+            Message::FinishCurrentTask {
+                id,
+                status: TaskStatus::Canceled,
+                ..
+            } => {
+                // Diagnostics of an interrupted build are partial, keep the previous ones
+                state.pending.remove(id);
+            }
+            Message::FinishCurrentTask { id, .. } => {
+                if let Some((target, diagnostics)) = state.pending.remove(id) {
+                    if diagnostics.is_empty() {
+                        state.targets.remove(&target);
+                    } else {
+                        state.targets.insert(target, diagnostics);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get diagnostics indexed by target, optionally only those about a given file
+    pub fn get(&self, file: Option<&Path>) -> HashMap<String, Vec<Diagnostic>> {
+        let state = self.0.lock().unwrap();
+        state
+            .targets
+            .iter()
+            .map(|(target, diagnostics)| {
+                let diagnostics = diagnostics
+                    .iter()
+                    .filter(|d| file.map_or(true, |file| d.file == file))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (target.clone(), diagnostics)
+            })
+            .filter(|(_, diagnostics)| !diagnostics.is_empty())
+            .collect()
+    }
+}
+
+/// Level of an output line that isn't a diagnostic, from its leading severity if any, e.g.
+/// `error: linker command failed`, `[Error] Build failed` or `ld: warning: ...`.
+pub(crate) fn output_level(line: &str) -> ContentLevel {
+    let line = line.trim().to_lowercase();
+    let line = line.strip_prefix("ld: ").unwrap_or(&line);
+    let is = |labels: &[&str]| {
+        labels.iter().any(|label| {
+            line.starts_with(&format!("{label}:")) || line.starts_with(&format!("[{label}]"))
+        })
+    };
+
+    if is(&["error", "fatal error"]) {
+        ContentLevel::Error
+    } else if is(&["warning", "warn"]) {
+        ContentLevel::Warn
+    } else {
+        ContentLevel::Info
+    }
+}
+
+#[test]
+fn test_parse_diagnostics() {
+    let diagnostic =
+        Diagnostic::parse("/tmp/App/Sources/ErrorView.swift:12:5: error: cannot find 'x' in scope")
+            .unwrap();
+    assert_eq!(
+        diagnostic.file,
+        PathBuf::from("/tmp/App/Sources/ErrorView.swift")
+    );
+    assert_eq!((diagnostic.line, diagnostic.column), (12, 5));
+    assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+    assert_eq!(diagnostic.message, "cannot find 'x' in scope");
+
+    let diagnostic =
+        Diagnostic::parse("[Warning] /tmp/App/main.swift:3:9: variable 'a' was never used")
+            .unwrap();
+    assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+    assert_eq!(diagnostic.message, "variable 'a' was never used");
+
+    assert!(Diagnostic::parse("[Compiling] ErrorView.swift").is_none());
+    assert!(Diagnostic::parse("Compiling App ErrorView.swift").is_none());
+    assert!(Diagnostic::parse("error: linker command failed with exit code 1").is_none());
+    assert_eq!(
+        output_level("[Compiling] ErrorView.swift"),
+        ContentLevel::Info
+    );
+    assert_eq!(
+        output_level("error: linker command failed"),
+        ContentLevel::Error
+    );
+    assert_eq!(
+        output_level("ld: warning: directory not found"),
+        ContentLevel::Warn
+    );
+}
+
+#[test]
+fn test_collect_diagnostics_notes() {
+    let lines = [
+        "/tmp/A.swift:4:10: error: invalid redeclaration of 'foo()'",
+        "/tmp/A.swift:2:10: note: 'foo()' previously declared here",
+        "/tmp/A.swift:4:10: error: invalid redeclaration of 'foo()'",
+        "/tmp/A.swift:2:10: note: 'foo()' previously declared here",
+        "/tmp/B.swift:1:1: warning: unused",
+    ];
+    let mut collector = DiagnosticCollector::default();
+    let mut diagnostics = lines
+        .iter()
+        .filter_map(|line| Diagnostic::parse(line))
+        .filter_map(|d| collector.push(d))
+        .collect::<Vec<_>>();
+    diagnostics.extend(collector.finish());
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].notes.len(), 1);
+    assert_eq!(diagnostics[0].notes[0].line, 2);
+    assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Warning);
+}
+
+#[test]
+fn test_diagnostics_store_replaced_on_rebuild() {
+    let store = DiagnosticsStore::default();
+    let build = |id, diagnostics: &[&str]| {
+        store.record(&Message::SetCurrentTask {
+            id,
+            kind: TaskKind::Build,
+            target: "App".into(),
+            status: TaskStatus::Processing,
+            timestamp: 0,
+        });
+        for line in diagnostics {
+            store.record(&Message::Diagnostic {
+                id,
+                diagnostic: Diagnostic::parse(line).unwrap(),
+                timestamp: 0,
+            });
+        }
+        store.record(&Message::FinishCurrentTask {
+            id,
+            status: TaskStatus::Failed,
+            timestamp: 0,
+        });
+    };
+
+    build(
+        1,
+        &["/tmp/A.swift:1:1: error: a", "/tmp/B.swift:1:1: error: b"],
+    );
+    assert_eq!(store.get(None)["App"].len(), 2);
+    assert_eq!(store.get(Some(Path::new("/tmp/B.swift")))["App"].len(), 1);
+
+    build(2, &["/tmp/A.swift:1:1: error: a"]);
+    assert!(store.get(Some(Path::new("/tmp/B.swift"))).is_empty());
+
+    build(3, &[]);
+    assert!(store.get(None).is_empty());
+}
+
+#[test]
+fn test_diagnostics_store_kept_on_cancel() {
+    let store = DiagnosticsStore::default();
+    for (id, status) in [(1, TaskStatus::Failed), (2, TaskStatus::Canceled)] {
+        store.record(&Message::SetCurrentTask {
+            id,
+            kind: TaskKind::Build,
+            target: "App".into(),
+            status: TaskStatus::Processing,
+            timestamp: 0,
+        });
+        store.record(&Message::Diagnostic {
+            id,
+            diagnostic: Diagnostic::parse(&format!("/tmp/A.swift:{id}:1: error: a")).unwrap(),
+            timestamp: 0,
+        });
+        store.record(&Message::FinishCurrentTask {
+            id,
+            status,
+            timestamp: 0,
+        });
+    }
+
+    assert_eq!(store.get(None)["App"][0].line, 1);
+    assert!(store.0.lock().unwrap().pending.is_empty());
+}
@@ -0,0 +1,420 @@
+use super::subscription::MessageInfo;
+use super::{replay::Replay, Message, Subscription, TaskKind};
+use crate::config::OverflowPolicy;
+use crate::util::token::{new_token, token_matches};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use typescript_type_def::TypeDef;
+
+/// How long a closed listener has to write its pending messages
+const CLOSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Write half of a connection listening to broadcast messages
+pub struct ListenerStream(Box<dyn AsyncWrite + Send + Unpin>);
+
+/// Tokens clients must connect to the broadcaster with, issued on registration
+#[derive(Debug, Clone, Default)]
+pub struct ListenerTokens(Arc<Mutex<HashMap<u32, String>>>);
+
+/// Connected clients message queues indexed by client id
+#[derive(Debug, Default)]
+pub(crate) struct ListenerSet {
+    queues: HashMap<u32, ListenerQueue>,
+    /// Number of the last connection, to tell reconnections of a client apart
+    connections: u64,
+    replay: Replay,
+    /// Kinds of the tasks under progress, to match task messages against subscriptions
+    tasks: HashMap<u32, TaskKind>,
+}
+
+/// Bounded outgoing message queue of a connected client, written by its own task so that a
+/// stalled client doesn't block delivery to others.
+#[derive(Debug)]
+pub(crate) struct ListenerQueue {
+    state: Arc<QueueState>,
+    writer: Option<JoinHandle<()>>,
+    subscription: Subscription,
+    connection: u64,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    messages: Mutex<VecDeque<Arc<str>>>,
+    notify: Notify,
+    closed: AtomicBool,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Message delivery statistics of a connected client
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct ListenerStats {
+    /// Client id
+    pub id: u32,
+    /// Messages waiting to be written
+    pub queued: u64,
+    /// Messages written
+    pub sent: u64,
+    /// Messages dropped because the queue was full
+    pub dropped: u64,
+}
+
+impl ListenerTokens {
+    /// Issue a new token for a given client id, replacing the previous one
+    pub fn issue(&self, id: u32) -> String {
+        let token = new_token();
+        self.0.lock().unwrap().insert(id, token.clone());
+        token
+    }
+
+    /// Check the token a client connected with
+    pub fn verify(&self, id: u32, token: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|expected| token_matches(expected, token))
+            .unwrap_or_default()
+    }
+
+    /// Revoke the token of a given client id
+    pub fn revoke(&self, id: u32) {
+        self.0.lock().unwrap().remove(&id);
+    }
+}
+
+impl ListenerSet {
+    /// Ensure a client isn't already connected with a stream that is still written to
+    pub fn available(&self, id: u32) -> Result<()> {
+        if self.queues.get(&id).map_or(false, |q| !q.is_closed()) {
+            return Err(Error::Unauthorized(format!(
+                "client {id} is already connected"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Add a client stream checked with [`Self::available`] and replay recent messages it
+    /// subscribed to, returns the connection number.
+    pub fn insert(&mut self, id: u32, stream: ListenerStream, subscription: Subscription) -> u64 {
+        let config = crate::config::get();
+        self.connections += 1;
+        let mut queue = ListenerQueue::spawn(id, stream, subscription);
+        queue.connection = self.connections;
+        for (info, message) in self.replay.messages() {
+            if queue.subscription.accepts(&info) {
+                queue.push(
+                    message,
+                    config.broadcast_queue_size,
+                    config.broadcast_overflow,
+                );
+            }
+        }
+        self.queues.insert(id, queue);
+        self.connections
+    }
+
+    /// Remove a client if it's still on the given connection
+    pub fn remove_connection(&mut self, id: u32, connection: u64) {
+        if self
+            .queues
+            .get(&id)
+            .map_or(false, |q| q.connection == connection)
+        {
+            tracing::info!("Disconnected [{id}]");
+            self.queues.remove(&id);
+        }
+    }
+
+    /// Replace the subscription of a client
+    pub fn subscribe(&mut self, id: u32, subscription: Subscription) {
+        if let Some(queue) = self.queues.get_mut(&id) {
+            tracing::debug!("[{id}] Subscribed to {subscription:?}");
+            queue.subscription = subscription;
+        }
+    }
+
+    /// Remove a client
+    pub fn remove(&mut self, id: &u32) -> Option<ListenerQueue> {
+        self.queues.remove(id)
+    }
+
+    /// Remove all clients
+    pub fn drain(&mut self) -> impl Iterator<Item = ListenerQueue> + '_ {
+        self.queues.drain().map(|(_, queue)| queue)
+    }
+
+    /// Queue a message on a given client or all clients, disconnecting clients that overflow
+    pub fn send(&mut self, id: Option<u32>, message: &Message, value: Arc<str>) {
+        let config = crate::config::get();
+        let (capacity, policy) = (config.broadcast_queue_size, config.broadcast_overflow);
+
+        if let Message::SetCurrentTask { id, kind, .. } = message {
+            self.tasks.insert(*id, kind.clone());
+        }
+        let task = message.task_id().and_then(|id| self.tasks.get(&id));
+        let info = MessageInfo::new(message, task);
+        if let Message::FinishCurrentTask { id, .. } = message {
+            self.tasks.remove(id);
+        }
+
+        let overflowed = match id {
+            Some(id) => match self.queues.get(&id) {
+                Some(queue) if !queue.subscription.accepts(&info) => vec![],
+                Some(queue) if !queue.push(value, capacity, policy) => vec![id],
+                Some(_) => vec![],
+                None => {
+                    tracing::error!("[CLIENT WITH {id} NOT FOUND]");
+                    vec![]
+                }
+            },
+            None => {
+                self.replay.record(message, &info, &value);
+                self.queues
+                    .iter()
+                    .filter(|(_, queue)| queue.subscription.accepts(&info))
+                    .filter(|(_, queue)| !queue.push(value.clone(), capacity, policy))
+                    .map(|(id, _)| *id)
+                    .collect()
+            }
+        };
+
+        for id in overflowed {
+            tracing::warn!("[{id}] Disconnected, message queue is full or closed");
+            if let Some(queue) = self.queues.remove(&id) {
+                queue.abort();
+            }
+        }
+    }
+
+    /// Get message delivery statistics of connected clients
+    pub fn stats(&self) -> Vec<ListenerStats> {
+        let mut stats = self
+            .queues
+            .iter()
+            .map(|(id, queue)| queue.stats(*id))
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|stats| stats.id);
+        stats
+    }
+}
+
+impl ListenerQueue {
+    /// Spawn a task writing queued messages to the given stream
+    pub fn spawn(id: u32, stream: ListenerStream, subscription: Subscription) -> Self {
+        let state = Arc::new(QueueState::default());
+        let writer = tokio::spawn(Self::write(id, state.clone(), stream));
+        Self {
+            state,
+            writer: Some(writer),
+            subscription,
+            connection: 0,
+        }
+    }
+
+    async fn write(id: u32, state: Arc<QueueState>, mut stream: ListenerStream) {
+        loop {
+            let message = state.messages.lock().unwrap().pop_front();
+            match message {
+                Some(message) => {
+                    let write = async {
+                        stream.write_all(message.as_bytes()).await?;
+                        stream.flush().await
+                    };
+                    if let Err(err) = write.await {
+                        tracing::info!("[{id}] Disconnected: {err}");
+                        break;
+                    }
+                    state.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                None if state.closed.load(Ordering::Acquire) => break,
+                None => state.notify.notified().await,
+            }
+        }
+        state.closed.store(true, Ordering::Release);
+        stream.shutdown().await.ok();
+    }
+
+    /// Queue a message, returns false if the client should be disconnected
+    pub fn push(&self, message: Arc<str>, capacity: usize, policy: OverflowPolicy) -> bool {
+        if self.state.closed.load(Ordering::Acquire) {
+            return false;
+        }
+
+        let mut messages = self.state.messages.lock().unwrap();
+        if messages.len() >= capacity {
+            self.state.dropped.fetch_add(1, Ordering::Relaxed);
+            match policy {
+                OverflowPolicy::DropOldest => {
+                    messages.pop_front();
+                }
+                OverflowPolicy::Disconnect => return false,
+            }
+        }
+        messages.push_back(message);
+        self.state.notify.notify_one();
+        true
+    }
+
+    /// Whether the stream is no longer written to
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.load(Ordering::Acquire)
+    }
+
+    /// Stop accepting messages, write pending ones and shutdown the stream
+    pub fn close(mut self) -> Option<JoinHandle<()>> {
+        self.close_writer()
+    }
+
+    fn close_writer(&mut self) -> Option<JoinHandle<()>> {
+        let mut writer = self.writer.take()?;
+        self.state.closed.store(true, Ordering::Release);
+        self.state.notify.notify_one();
+
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        Some(runtime.spawn(async move {
+            tokio::select! {
+                _ = &mut writer => {},
+                _ = tokio::time::sleep(CLOSE_TIMEOUT) => writer.abort(),
+            }
+        }))
+    }
+
+    /// Drop pending messages and the stream
+    pub fn abort(mut self) {
+        if let Some(writer) = self.writer.take() {
+            writer.abort();
+        }
+    }
+
+    /// Get message delivery statistics
+    pub fn stats(&self, id: u32) -> ListenerStats {
+        ListenerStats {
+            id,
+            queued: self.state.messages.lock().unwrap().len() as u64,
+            sent: self.state.sent.load(Ordering::Relaxed),
+            dropped: self.state.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for ListenerQueue {
+    fn drop(&mut self) {
+        self.close_writer();
+    }
+}
+
+impl ListenerStream {
+    pub fn new(stream: impl AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self(Box::new(stream))
+    }
+
+    /// Write an error line and close the stream
+    pub async fn reject(mut self, error: Error) {
+        tracing::error!("Rejected client: {error}");
+        if let Ok(mut line) = serde_json::to_string(&crate::ServerError::from(&error)) {
+            line.push('\n');
+            self.write_all(line.as_bytes()).await.ok();
+        }
+        self.shutdown().await.ok();
+    }
+}
+
+impl std::fmt::Debug for ListenerStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ListenerStream")
+    }
+}
+
+impl AsyncWrite for ListenerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_shutdown(cx)
+    }
+}
+
+#[test]
+fn test_available_rejects_connected_clients() {
+    let mut listeners = ListenerSet::default();
+    assert!(listeners.available(1).is_ok());
+    listeners.queues.insert(1, test_queue());
+    assert!(listeners.available(1).is_err());
+    assert!(listeners.available(2).is_ok());
+    listeners.queues[&1]
+        .state
+        .closed
+        .store(true, Ordering::Release);
+    assert!(listeners.available(1).is_ok());
+}
+
+#[cfg(test)]
+fn test_queue() -> ListenerQueue {
+    ListenerQueue {
+        state: Default::default(),
+        writer: None,
+        subscription: Default::default(),
+        connection: 0,
+    }
+}
+
+#[test]
+fn test_queue_drop_oldest_on_overflow() {
+    let queue = test_queue();
+    for message in ["a", "b", "c"] {
+        assert!(queue.push(message.into(), 2, OverflowPolicy::DropOldest));
+    }
+
+    let messages = queue.state.messages.lock().unwrap().clone();
+    assert_eq!(messages, [Arc::from("b"), Arc::from("c")]);
+    assert_eq!(queue.stats(1).dropped, 1);
+}
+
+#[test]
+fn test_queue_disconnect_on_overflow() {
+    let queue = test_queue();
+    assert!(queue.push("a".into(), 2, OverflowPolicy::Disconnect));
+    assert!(queue.push("b".into(), 2, OverflowPolicy::Disconnect));
+    assert!(!queue.push("c".into(), 2, OverflowPolicy::Disconnect));
+
+    assert_eq!(queue.state.messages.lock().unwrap().len(), 2);
+    assert_eq!(queue.stats(1).dropped, 1);
+}
+
+#[test]
+fn test_listener_stats() {
+    let mut listeners = ListenerSet::default();
+    for (id, count) in [(2, 3), (1, 1)] {
+        let queue = test_queue();
+        for _ in 0..count {
+            queue.push("message".into(), 2, OverflowPolicy::DropOldest);
+        }
+        listeners.queues.insert(id, queue);
+    }
+
+    let stats = listeners
+        .stats()
+        .into_iter()
+        .map(|s| (s.id, s.queued, s.dropped))
+        .collect::<Vec<_>>();
+    assert_eq!(stats, [(1, 1, 0), (2, 2, 1)]);
+}
@@ -1,3 +1,4 @@
+use super::{Diagnostic, TestCase, TestSuite};
 use crate::{BuildSettings, ProjectInfo, Runners};
 use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
@@ -28,19 +29,58 @@ pub enum Message {
     OpenLogger,
     /// Reload Language server
     ReloadLspServer,
-    /// Set Current Task
+    /// Start a task, tasks may overlap and are told apart by their id
     SetCurrentTask {
+        id: u32,
         kind: TaskKind,
         target: String,
         status: TaskStatus,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
     },
-    /// Update Current Task
+    /// Update a task with a new line of output
     UpdateCurrentTask {
+        id: u32,
         content: String,
         level: ContentLevel,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
     },
+    /// Estimated progress of a task, sent as its steps complete
+    TaskProgress {
+        id: u32,
+        completed: u64,
+        total: u64,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
+    },
+    /// Compiler diagnostic parsed from a task output
+    Diagnostic {
+        id: u32,
+        diagnostic: Diagnostic,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
+    },
+    /// Test suite of a test task started or finished
+    TestSuite {
+        id: u32,
+        suite: TestSuite,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
+    },
+    /// Test case of a test task started or finished
+    TestCase {
+        id: u32,
+        case: TestCase,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
+    },
+    /// Finish a task
     FinishCurrentTask {
+        id: u32,
         status: TaskStatus,
+        /// Milliseconds since unix epoch
+        timestamp: u64,
     },
     /// Notify client that something is being watched
     SetWatching {
@@ -49,7 +89,7 @@ pub enum Message {
     },
     /// Notification to client to update a state with the given value
     SetState(State),
-    /// Internal!
+    /// Internal! Disconnect a client, or all clients if no id is given
     #[serde(skip)]
     Disconnect,
 }
@@ -65,6 +105,21 @@ pub enum TaskKind {
     Compile,
     /// Generate xcodeproj
     Generate,
+    /// Test Task
+    Test,
+}
+
+/// Information about a task currently under progress
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct TaskInfo {
+    /// Task id, unique within the daemon lifetime
+    pub id: u32,
+    /// Task kind
+    pub kind: TaskKind,
+    /// Target the task is processing
+    pub target: String,
+    /// When the task started, in milliseconds since unix epoch
+    pub started: u64,
 }
 
 /// What the status of task is currently under progress?
@@ -76,6 +131,8 @@ pub enum TaskStatus {
     Succeeded,
     /// Processing Task,
     Processing,
+    /// Task Canceled, e.g. on shutdown
+    Canceled,
 }
 
 /// What a given content level is? for whether to log/show it
@@ -93,6 +150,42 @@ pub enum ContentLevel {
     Error,
 }
 
+impl Message {
+    /// Message type, as sent to clients
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Message::Notify { .. } => "Notify",
+            Message::Log { .. } => "Log",
+            Message::OpenLogger => "OpenLogger",
+            Message::ReloadLspServer => "ReloadLspServer",
+            Message::SetCurrentTask { .. } => "SetCurrentTask",
+            Message::UpdateCurrentTask { .. } => "UpdateCurrentTask",
+            Message::TaskProgress { .. } => "TaskProgress",
+            Message::Diagnostic { .. } => "Diagnostic",
+            Message::TestSuite { .. } => "TestSuite",
+            Message::TestCase { .. } => "TestCase",
+            Message::FinishCurrentTask { .. } => "FinishCurrentTask",
+            Message::SetWatching { .. } => "SetWatching",
+            Message::SetState(_) => "SetState",
+            Message::Disconnect => "Disconnect",
+        }
+    }
+
+    /// Id of the task a message belongs to, if any
+    pub fn task_id(&self) -> Option<u32> {
+        match self {
+            Message::SetCurrentTask { id, .. }
+            | Message::UpdateCurrentTask { id, .. }
+            | Message::TaskProgress { id, .. }
+            | Message::Diagnostic { id, .. }
+            | Message::TestSuite { id, .. }
+            | Message::TestCase { id, .. }
+            | Message::FinishCurrentTask { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
 impl From<String> for Message {
     fn from(value: String) -> Self {
         Self::Notify {
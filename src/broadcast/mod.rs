@@ -1,21 +1,36 @@
+mod diagnostic;
+mod listener;
 mod message;
+mod progress;
+mod replay;
+mod subscription;
 mod task;
+mod xctest;
 
+pub use self::diagnostic::{Diagnostic, DiagnosticNote, DiagnosticSeverity, DiagnosticsStore};
+pub use self::listener::{ListenerStats, ListenerStream, ListenerTokens};
 pub use self::message::*;
+pub use self::subscription::Subscription;
+pub use self::xctest::{TestCase, TestFailure, TestStatus, TestSuite};
 pub use task::*;
 use tracing::instrument;
 
 use crate::util::extensions::PathExt;
-use crate::Result;
+use crate::{Error, Result};
+use listener::ListenerSet;
 use process_stream::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::{mpsc::*, Mutex, Notify};
+use tokio::sync::{mpsc::*, Notify};
 use tokio::task::JoinHandle;
 
+/// Connected clients message queues and messages replayed to new clients
+type Listeners = Arc<Mutex<ListenerSet>>;
+
 /// Broadcast server to send task to clients
 #[derive(Debug)]
 pub struct Broadcast {
@@ -27,27 +42,38 @@ pub struct Broadcast {
     pub handle: JoinHandle<()>,
     /// Server handler
     pub server: JoinHandle<()>,
-    /// Sender to be used within the server to write items to file_path
+    /// Sender to be used within the server to write items to file_path.
+    ///
+    /// Unbounded as messages are sent from sync code, but it doesn't grow with slow clients: the
+    /// messages handler never waits on them, it only serializes each message and pushes it to
+    /// the bounded queues of clients, whose writers run in their own tasks.
     tx: UnboundedSender<(Option<u32>, Message)>,
     /// Abort notifier to stop the logger
     abort: Arc<Notify>,
+    /// Notifier to cancel running tasks and abort their processes
+    cancel: Arc<Notify>,
+    /// Notifiers to cancel a single running task and abort its process, indexed by task id
+    cancels: Mutex<HashMap<u32, Arc<Notify>>>,
+    /// Notifier to notify that the messages handler has stopped
+    finished: Arc<Notify>,
     /// Socket listeners
-    #[allow(dead_code)]
-    listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+    listeners: Listeners,
+    /// Tokens clients must connect with
+    tokens: ListenerTokens,
+    /// Tasks currently under progress
+    tasks: ActiveTasks,
+    /// Diagnostics of the last build of each target
+    diagnostics: DiagnosticsStore,
 }
 
 impl Broadcast {
-    pub const ROOT: &'static str = "/private/tmp/xbase";
-
     #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name = root.as_ref().name().unwrap()))]
     pub async fn new(root: impl AsRef<Path>) -> Result<Self> {
         let (tx, rx) = unbounded_channel();
         let name = format!("{}.socket", root.as_ref().unique_name().unwrap());
-        let base = PathBuf::from(Self::ROOT);
-
-        if !base.exists() {
-            tokio::fs::create_dir(Self::ROOT).await?;
-        }
+        let config = crate::config::get();
+        config.ensure_runtime_dirs()?;
+        let base = config.broadcast_root.clone();
 
         let address = base.join(name);
         let name = root.as_ref().name().unwrap();
@@ -57,19 +83,24 @@ impl Broadcast {
         };
 
         let abort: Arc<Notify> = Default::default();
-        let listeners: Arc<Mutex<HashMap<u32, UnixStream>>> = Default::default();
+        let finished: Arc<Notify> = Default::default();
+        let listeners: Listeners = Default::default();
+        let tokens = ListenerTokens::default();
 
         let listener = UnixListener::bind(&address)?;
+        crate::config::set_private_permissions(&address)?;
         let server = tokio::spawn(Self::start_server(
             name.clone(),
             listener,
             abort.clone(),
             listeners.clone(),
+            tokens.clone(),
         ));
         let handle = tokio::spawn(Self::start_messages_handler(
             name,
             rx,
             abort.clone(),
+            finished.clone(),
             listeners.clone(),
         ));
 
@@ -79,10 +110,16 @@ impl Broadcast {
             root: root.as_ref().to_path_buf(),
             tx,
             abort,
+            cancel: Default::default(),
+            cancels: Default::default(),
+            finished,
             handle,
             listeners,
+            tokens,
             server,
             address,
+            tasks: Default::default(),
+            diagnostics: Default::default(),
         })
     }
 
@@ -92,7 +129,8 @@ impl Broadcast {
         name: String,
         listener: UnixListener,
         abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+        listeners: Listeners,
+        tokens: ListenerTokens,
     ) {
         loop {
             tokio::select! {
@@ -100,92 +138,222 @@ impl Broadcast {
                     tracing::info!("[Dropped]");
                     break
                 },
-                Ok((mut stream, _)) = listener.accept() => {
-                    let mut listeners = listeners.lock().await;
+                Ok((stream, _)) = listener.accept() => {
+                    tokio::spawn(Self::connect(stream, listeners.clone(), tokens.clone()));
+                }
+            }
+        }
+    }
 
-                    let (reader, _) = stream.split();
+    /// Authenticate client and start sending it messages, then keep reading subscription updates
+    /// until the client disconnects.
+    ///
+    /// Clients must first write their id and the broadcast token they registered with, separated
+    /// by a space. Unknown ids, invalid tokens and already connected ids get an error line.
+    /// Clients receive every message until they write a subscription line.
+    async fn connect(stream: UnixStream, listeners: Listeners, tokens: ListenerTokens) {
+        let (reader, writer) = stream.into_split();
+        let writer = ListenerStream::new(writer);
+        let mut lines = BufReader::new(reader).lines();
+
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => return,
+            Err(err) => return tracing::error!("Failed to connect a client: {err}"),
+        };
+        let id = match Self::authenticate(&line, &tokens) {
+            Ok(id) => id,
+            Err(err) => return writer.reject(err).await,
+        };
+        let connection = {
+            let mut listeners = listeners.lock().unwrap();
+            match listeners.available(id) {
+                Ok(()) => Ok(listeners.insert(id, writer, Subscription::default())),
+                Err(err) => Err((writer, err)),
+            }
+        };
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err((writer, err)) => return writer.reject(err).await,
+        };
+        tracing::info!("Connected [{id}]");
 
-                    let mut buf = String::default();
-                    let mut reader = BufReader::new(reader);
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(subscription) = Self::parse_subscription(id, &line) {
+                listeners.lock().unwrap().subscribe(id, subscription);
+            }
+        }
 
-                    // let reader = BufReader::new(&stream);
-                    match reader.read_line(&mut buf).await {
-                        Ok(_) => match buf.trim().parse::<u32>() {
-                            Ok(id) =>  {
-                                tracing::info!("Connected [{id}]");
-                                listeners.insert(id, stream);
-                            }
-                            Err(err) => {
-                                tracing::error!("Failed to parse id as u32: {err}");
+        listeners.lock().unwrap().remove_connection(id, connection);
+    }
 
-                            }
-                        }
-                        Err(err) => {
-                            tracing::error!("Failed to connect a client: {err}");
-                        }
-                    }
-                }
-            }
+    /// Parse and verify `<id> <token>` handshake line
+    fn authenticate(line: &str, tokens: &ListenerTokens) -> Result<u32> {
+        let (id, token) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let id = id
+            .parse::<u32>()
+            .map_err(|err| Error::Unauthorized(format!("invalid client id {id:?}: {err}")))?;
+
+        if !tokens.verify(id, token) {
+            return Err(Error::Unauthorized(format!(
+                "unknown client {id} or invalid token"
+            )));
         }
+        Ok(id)
+    }
+
+    fn parse_subscription(id: u32, line: &str) -> Option<Subscription> {
+        serde_json::from_str(line)
+            .map_err(|err| tracing::error!("[{id}] Invalid subscription {line}: {err}"))
+            .ok()
     }
 
     /// Start message handler
-    /// This loop receive messages and write them on all connected clients.
+    /// This loop receive messages and queue them on all connected clients.
+    ///
+    /// A Disconnect message without an id disconnect all clients and stop the loop, after
+    /// writing every message sent before it.
     #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name=name))]
     async fn start_messages_handler(
         name: String,
         mut rx: UnboundedReceiver<(Option<u32>, Message)>,
         abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
+        finished: Arc<Notify>,
+        listeners: Listeners,
     ) {
         loop {
             tokio::select! {
                 _ = abort.notified() => { break; },
                 result = rx.recv() => match result {
                     None => break,
-                    Some((id, message)) => {
-                        let listeners =  listeners.clone();
-                        let mut listeners = listeners.lock().await;
-                        if let Message::Disconnect = message {
-                            listeners.remove(&id.unwrap());
-                            continue;
+                    Some((id, Message::Disconnect)) => match id {
+                        Some(id) => {
+                            if let Some(queue) = listeners.lock().unwrap().remove(&id) {
+                                queue.close();
+                            }
                         }
-
-                        match serde_json::to_string(&message) {
-                            Ok(mut value) => {
-                                tracing::trace!("{value}");
-                                value.push('\n');
-                                if let Some(id) = id {
-                                    if let Some(stream) = listeners.get_mut(&id) {
-                                        stream.write_all(value.as_bytes()).await.ok();
-                                        stream.flush().await.ok();
-                                    } else {
-                                        tracing::error!("[CLIENT WITH {id} NOT FOUND]")
-                                    }
-                                } else {
-                                    for (_, listener) in listeners.iter_mut() {
-                                        listener.write_all(value.as_bytes()).await.ok();
-                                        listener.flush().await.ok();
-                                    };
-                                }
-                            },
-                            Err(err) => tracing::warn!("SendError: `{message:?}` = `{err}`"),
+                        None => {
+                            let closing = listeners
+                                .lock()
+                                .unwrap()
+                                .drain()
+                                .filter_map(|queue| queue.close())
+                                .collect::<Vec<_>>();
+                            futures::future::join_all(closing).await;
+                            break;
                         }
+                    },
+                    Some((id, message)) => match serde_json::to_string(&message) {
+                        Ok(mut value) => {
+                            tracing::trace!("{value}");
+                            value.push('\n');
+                            listeners.lock().unwrap().send(id, &message, value.into());
+                        },
+                        Err(err) => tracing::warn!("SendError: `{message:?}` = `{err}`"),
                     }
                 }
             }
         }
+        finished.notify_one();
     }
 
     pub fn send(&self, id: Option<u32>, message: Message) {
+        self.diagnostics.record(&message);
+        match &message {
+            Message::SetCurrentTask {
+                id,
+                kind,
+                target,
+                timestamp,
+                ..
+            } => self.tasks.start(TaskInfo {
+                id: *id,
+                kind: kind.clone(),
+                target: target.clone(),
+                started: *timestamp,
+            }),
+            Message::FinishCurrentTask { id, .. } => {
+                self.tasks.finish(*id);
+            }
+            _ => {}
+        };
         self.tx.send((id, message)).ok();
     }
 
-    /// Explicitly Abort/Consume logger
+    /// Get tasks currently under progress
+    pub fn tasks(&self) -> &ActiveTasks {
+        &self.tasks
+    }
+
+    /// Get diagnostics of the last build of each target
+    pub fn diagnostics(&self) -> &DiagnosticsStore {
+        &self.diagnostics
+    }
+
+    /// Attach a stream to receive messages sent to the given client id
+    pub async fn attach(&self, id: u32, stream: ListenerStream, subscription: Subscription) {
+        let rejected = {
+            let mut listeners = self.listeners.lock().unwrap();
+            match listeners.available(id) {
+                Ok(()) => {
+                    listeners.insert(id, stream, subscription);
+                    None
+                }
+                Err(err) => Some((stream, err)),
+            }
+        };
+        match rejected {
+            Some((stream, err)) => stream.reject(err).await,
+            None => tracing::info!("Attached [{id}]"),
+        }
+    }
+
+    /// Get tokens clients must connect with
+    pub fn tokens(&self) -> &ListenerTokens {
+        &self.tokens
+    }
+
+    /// Get message delivery statistics of connected clients
+    pub fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.listeners.lock().unwrap().stats()
+    }
+
+    /// Explicitly Abort/Consume logger, canceling running tasks
     pub fn abort(&self) {
+        self.cancel.notify_waiters();
         self.abort.notify_waiters();
     }
 
+    /// Cancel running tasks, write pending messages including their Canceled finish, then
+    /// disconnect all clients.
+    pub async fn shutdown(&self) {
+        self.cancel.notify_waiters();
+        let canceled = async {
+            while !self.tasks.list().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        if tokio::time::timeout(Duration::from_secs(1), canceled)
+            .await
+            .is_err()
+        {
+            tracing::warn!("Timed out waiting for running tasks to be canceled");
+            for task in self.tasks.list() {
+                self.cancel_task(task.id);
+            }
+        }
+
+        self.send(None, Message::Disconnect);
+        let finished = self.finished.notified();
+        if tokio::time::timeout(Duration::from_secs(1), finished)
+            .await
+            .is_err()
+        {
+            tracing::warn!("Timed out waiting for pending messages to be written");
+        }
+        self.abort();
+    }
+
     /// Get a reference to the logger's project root.
     #[must_use]
     pub fn root(&self) -> &PathBuf {
@@ -207,25 +375,55 @@ impl Broadcast {
         self.send(None, Message::ReloadLspServer)
     }
 
-    pub fn update_current_task<S: AsRef<str>>(&self, content: S, level: ContentLevel) {
+    /// Update a task under progress with a new line of output
+    pub fn update_task<S: AsRef<str>>(&self, id: u32, content: S, level: ContentLevel) {
         self.send(
             None,
             Message::UpdateCurrentTask {
+                id,
                 content: content.as_ref().into(),
                 level,
+                timestamp: task::timestamp(),
             },
         )
     }
 
-    pub fn finish_current_task(&self, success: bool) {
+    /// Finish a task under progress with whether it was successfull or not
+    pub fn finish_task(&self, id: u32, success: bool) {
+        let status = if success {
+            TaskStatus::Succeeded
+        } else {
+            TaskStatus::Failed
+        };
+        self.end_task(id, status);
+    }
+
+    /// Cancel running tasks of a given kind and target, aborting their processes
+    pub fn cancel_tasks(&self, kind: &TaskKind, target: &str) {
+        let cancels = self.cancels.lock().unwrap();
+        self.tasks
+            .list()
+            .into_iter()
+            .filter(|task| &task.kind == kind && task.target == target)
+            .filter_map(|task| cancels.get(&task.id))
+            .for_each(|cancel| cancel.notify_one());
+    }
+
+    /// Finish a task that was canceled before its process exited
+    pub fn cancel_task(&self, id: u32) {
+        self.end_task(id, TaskStatus::Canceled);
+    }
+
+    fn end_task(&self, id: u32, status: TaskStatus) {
+        if !self.tasks.contains(id) {
+            return;
+        }
         self.send(
             None,
             Message::FinishCurrentTask {
-                status: if success {
-                    TaskStatus::Succeeded
-                } else {
-                    TaskStatus::Failed
-                },
+                id,
+                status,
+                timestamp: task::timestamp(),
             },
         )
     }
@@ -0,0 +1,82 @@
+/// Estimate of how far a build task went, from its output lines.
+///
+/// `swift build` prefixes steps with `[completed/total]`. xcodebuild doesn't, so compile and link
+/// steps are counted against an estimated number of steps (e.g. the target sources count).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Progress {
+    completed: u64,
+    total: u64,
+}
+
+impl Progress {
+    /// Create progress with an estimated number of steps, if known
+    pub fn new(total: Option<u64>) -> Self {
+        Self {
+            completed: 0,
+            total: total.unwrap_or_default(),
+        }
+    }
+
+    /// Advance progress with an output line, returns (completed, total) if it changed
+    pub fn step(&mut self, line: &str) -> Option<(u64, u64)> {
+        let line = line.trim();
+        if let Some((completed, total)) = Self::counter(line) {
+            if (completed, total) == (self.completed, self.total) {
+                return None;
+            }
+            self.completed = completed;
+            self.total = total;
+        } else if Self::is_step(line) && self.total > 0 {
+            self.completed += 1;
+            // Estimates can be off, never report completion before the task finishes
+            if self.completed >= self.total {
+                self.total = self.completed + 1;
+            }
+        } else {
+            return None;
+        }
+
+        Some((self.completed, self.total))
+    }
+
+    /// Parse `[completed/total]` prefix of swift build output
+    fn counter(line: &str) -> Option<(u64, u64)> {
+        let (counter, _) = line.strip_prefix('[')?.split_once(']')?;
+        let (completed, total) = counter.split_once('/')?;
+        let (completed, total): (u64, u64) = (completed.parse().ok()?, total.parse().ok()?);
+        (completed <= total && total > 0).then(|| (completed, total))
+    }
+
+    /// Whether a line starts a compile or link step, e.g. `[Compiling] App.swift`
+    fn is_step(line: &str) -> bool {
+        let line = line.trim_start_matches('[');
+        ["Compiling", "Compile", "Linking", "Link"]
+            .iter()
+            .any(|step| line.starts_with(step))
+    }
+}
+
+#[test]
+fn test_progress_swift_build() {
+    let mut progress = Progress::new(None);
+    assert_eq!(progress.step("Fetching https://github.com/a/b"), None);
+    assert_eq!(
+        progress.step("[1/12] Compiling App App.swift"),
+        Some((1, 12))
+    );
+    assert_eq!(progress.step("[1/12] Compiling App App.swift"), None);
+    assert_eq!(progress.step("[12/12] Linking App"), Some((12, 12)));
+    assert_eq!(progress.step("[Debug] ErrorView.swift"), None);
+}
+
+#[test]
+fn test_progress_xcodebuild() {
+    let mut progress = Progress::new(Some(3));
+    assert_eq!(progress.step("[Compiling] App.swift"), Some((1, 3)));
+    assert_eq!(progress.step("Note: Using new build system"), None);
+    assert_eq!(progress.step("[Compiling] View.swift"), Some((2, 3)));
+    assert_eq!(progress.step("[Linking] App"), Some((3, 4)));
+
+    let mut progress = Progress::new(None);
+    assert_eq!(progress.step("[Compiling] App.swift"), None);
+}
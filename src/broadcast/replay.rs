@@ -0,0 +1,104 @@
+use super::subscription::MessageInfo;
+use super::Message;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Number of recent messages replayed to newly connected clients
+const REPLAY_SIZE: usize = 256;
+
+/// Recent broadcast messages replayed to newly connected clients, so that they can catch up with
+/// a task already under progress.
+#[derive(Debug, Default)]
+pub(crate) struct Replay {
+    /// Recent messages, oldest first
+    messages: VecDeque<(MessageInfo, Arc<str>)>,
+    /// SetCurrentTask messages of tasks under progress, kept after they leave recent messages
+    tasks: Vec<(u32, MessageInfo, Arc<str>)>,
+}
+
+impl Replay {
+    /// Record a message sent to all clients
+    pub fn record(&mut self, message: &Message, info: &MessageInfo, value: &Arc<str>) {
+        match message {
+            Message::SetCurrentTask { id, .. } => {
+                self.tasks.push((*id, info.clone(), value.clone()))
+            }
+            Message::FinishCurrentTask { id, .. } => self.tasks.retain(|(task, ..)| task != id),
+            Message::Notify { .. }
+            | Message::Log { .. }
+            | Message::UpdateCurrentTask { .. }
+            | Message::Diagnostic { .. }
+            | Message::TestSuite { .. }
+            | Message::TestCase { .. } => {}
+            _ => return,
+        };
+
+        if self.messages.len() >= REPLAY_SIZE {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((info.clone(), value.clone()));
+    }
+
+    /// Messages to replay, starting with tasks under progress no longer in recent messages
+    pub fn messages(&self) -> Vec<(MessageInfo, Arc<str>)> {
+        let pinned = self
+            .tasks
+            .iter()
+            .filter(|(_, _, task)| !self.messages.iter().any(|(_, m)| Arc::ptr_eq(m, task)))
+            .map(|(_, info, task)| (info, task));
+
+        pinned
+            .chain(self.messages.iter().map(|(info, m)| (info, m)))
+            .map(|(info, m)| (info.clone(), m.clone()))
+            .collect()
+    }
+}
+
+#[test]
+fn test_replay_pins_tasks_under_progress() {
+    use super::{ContentLevel, TaskKind, TaskStatus};
+    let mut replay = Replay::default();
+    let mut record = |message: Message| {
+        let value: Arc<str> = serde_json::to_string(&message).unwrap().into();
+        let info = MessageInfo::new(&message, None);
+        replay.record(&message, &info, &value);
+        replay
+            .messages()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>()
+    };
+
+    let set = |id, target: &str| Message::SetCurrentTask {
+        id,
+        kind: TaskKind::Build,
+        target: target.into(),
+        status: TaskStatus::Processing,
+        timestamp: 0,
+    };
+    record(set(1, "App"));
+    record(set(2, "Tests"));
+    for i in 0..REPLAY_SIZE {
+        record(Message::UpdateCurrentTask {
+            id: 1,
+            content: format!("line {i}"),
+            level: ContentLevel::Info,
+            timestamp: 0,
+        });
+    }
+
+    let messages = record(Message::OpenLogger);
+    assert_eq!(messages.len(), REPLAY_SIZE + 2);
+    assert!(messages[0].contains("App"));
+    assert!(messages[1].contains("Tests"));
+    assert!(messages[2].contains("line 0"));
+
+    let messages = record(Message::FinishCurrentTask {
+        id: 1,
+        status: TaskStatus::Succeeded,
+        timestamp: 0,
+    });
+    assert_eq!(messages.len(), REPLAY_SIZE + 1);
+    assert!(messages[0].contains("Tests"));
+    assert!(messages[REPLAY_SIZE].contains("FinishCurrentTask"));
+}
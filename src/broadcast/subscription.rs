@@ -0,0 +1,98 @@
+use super::{ContentLevel, Message, TaskKind};
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+/// Broadcast messages a client wants to receive.
+///
+/// Sent as a json line over the broadcast socket after the client id, and again at any time to
+/// replace it. Filters left empty accept everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+#[serde(default)]
+pub struct Subscription {
+    /// Minimum level of Notify, Log and UpdateCurrentTask messages
+    pub level: Option<ContentLevel>,
+    /// Message types to receive, e.g. Notify
+    pub messages: Vec<String>,
+    /// Task kinds to receive SetCurrentTask, UpdateCurrentTask, TaskProgress and FinishCurrentTask
+    /// messages of
+    pub tasks: Vec<TaskKind>,
+}
+
+/// What a subscription is matched against
+#[derive(Debug, Clone)]
+pub(crate) struct MessageInfo {
+    kind: &'static str,
+    level: Option<ContentLevel>,
+    task: Option<TaskKind>,
+}
+
+impl MessageInfo {
+    /// Create message info with the kind of the task the message belongs to
+    pub fn new(message: &Message, task: Option<&TaskKind>) -> Self {
+        let (level, task) = match message {
+            Message::Notify { level, .. } | Message::Log { level, .. } => (Some(level), None),
+            Message::UpdateCurrentTask { level, .. } => (Some(level), task),
+            Message::SetCurrentTask { kind, .. } => (None, Some(kind)),
+            Message::TaskProgress { .. }
+            | Message::Diagnostic { .. }
+            | Message::TestSuite { .. }
+            | Message::TestCase { .. }
+            | Message::FinishCurrentTask { .. } => (None, task),
+            _ => (None, None),
+        };
+
+        Self {
+            kind: message.kind(),
+            level: level.cloned(),
+            task: task.cloned(),
+        }
+    }
+}
+
+impl Subscription {
+    /// Whether a message should be sent to the client
+    pub(crate) fn accepts(&self, info: &MessageInfo) -> bool {
+        let level = match (&self.level, &info.level) {
+            (Some(min), Some(level)) => level >= min,
+            _ => true,
+        };
+        let kind = self.messages.is_empty() || self.messages.iter().any(|k| k == info.kind);
+        let task = match &info.task {
+            Some(task) => self.tasks.is_empty() || self.tasks.contains(task),
+            None => true,
+        };
+
+        level && kind && task
+    }
+}
+
+#[test]
+fn test_subscription_filters() {
+    let subscription = Subscription {
+        level: Some(ContentLevel::Info),
+        messages: vec![],
+        tasks: vec![TaskKind::Run],
+    };
+    let log = |level| Message::Log {
+        content: "Compiling".into(),
+        level,
+    };
+    let update = Message::UpdateCurrentTask {
+        id: 1,
+        content: "Compiling".into(),
+        level: ContentLevel::Info,
+        timestamp: 0,
+    };
+
+    assert!(!subscription.accepts(&MessageInfo::new(&log(ContentLevel::Trace), None)));
+    assert!(subscription.accepts(&MessageInfo::new(&log(ContentLevel::Error), None)));
+    assert!(!subscription.accepts(&MessageInfo::new(&update, Some(&TaskKind::Build))));
+    assert!(subscription.accepts(&MessageInfo::new(&update, Some(&TaskKind::Run))));
+
+    let subscription = Subscription {
+        messages: vec!["Notify".into()],
+        ..Default::default()
+    };
+    assert!(!subscription.accepts(&MessageInfo::new(&log(ContentLevel::Error), None)));
+    assert!(subscription.accepts(&MessageInfo::new(&"Built".into(), None)));
+}
@@ -1,39 +1,87 @@
+use super::diagnostic::{output_level, DiagnosticCollector};
+use super::progress::Progress;
+use super::xctest::{TestEvent, TestParser};
 use super::*;
+use crate::error::STDERR_TAIL_LINES;
+use crate::BuildReport;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Id of the next task to be created
+static NEXT_TASK_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Outcome of a task process, sent once it exits or the task is canceled
+#[derive(Debug, Clone, Default)]
+pub struct TaskOutcome {
+    /// Whether the process exited successfully
+    pub success: bool,
+    /// Exit code of the process, if it exited
+    pub exit_code: Option<i32>,
+    /// Last lines the process wrote to stderr
+    pub stderr: Vec<String>,
+}
+
+/// Tasks currently under progress, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct ActiveTasks(Arc<Mutex<Vec<TaskInfo>>>);
 
 #[derive(Debug, Clone)]
 pub struct Task {
-    #[allow(dead_code)]
+    id: u32,
     task: TaskKind,
     #[allow(dead_code)]
     target: String,
     inner: Arc<Broadcast>,
+    /// Estimated number of steps, used to report progress of output without step counters
+    steps: Option<u64>,
+    /// Reports to write once the task finishes
+    report: Option<BuildReport>,
 }
 
 impl Task {
     /// Create a new task with it's kind, the target, and broadcast to send message through
     pub fn new(task: TaskKind, target: &str, broadcast: Arc<Broadcast>) -> Task {
-        broadcast
-            .tx
-            .send((
-                None,
-                Message::SetCurrentTask {
-                    kind: task.clone(),
-                    target: target.into(),
-                    status: TaskStatus::Processing,
-                },
-            ))
-            .ok();
+        let id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+        broadcast.send(
+            None,
+            Message::SetCurrentTask {
+                id,
+                kind: task.clone(),
+                target: target.into(),
+                status: TaskStatus::Processing,
+                timestamp: timestamp(),
+            },
+        );
         Task {
+            id,
             task,
             target: target.into(),
             inner: broadcast,
+            steps: None,
+            report: None,
         }
     }
 
+    /// Set the estimated number of steps (e.g. compile and link steps) the task goes through
+    pub fn with_steps(mut self, steps: Option<u64>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Set the reports to write with the task outcome and diagnostics once it finishes
+    pub fn with_report(mut self, report: Option<BuildReport>) -> Self {
+        self.report = report;
+        self
+    }
+
+    /// Task id, carried by all messages of this task
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     fn update<S: AsRef<str>>(&self, level: ContentLevel, content: S) {
-        let content = content.as_ref().into();
-        let message = Message::UpdateCurrentTask { content, level };
-        self.inner.tx.send((None, message)).ok();
+        self.inner.update_task(self.id, content, level);
     }
 
     /// Update CurrentTask with info and content
@@ -60,63 +108,150 @@ impl Task {
         self.update(ContentLevel::Error, content);
     }
 
+    /// Report task progress
+    pub fn progress(&self, completed: u64, total: u64) {
+        self.inner.send(
+            None,
+            Message::TaskProgress {
+                id: self.id,
+                completed,
+                total,
+                timestamp: timestamp(),
+            },
+        );
+    }
+
+    /// Report a compiler diagnostic parsed from task output
+    pub fn diagnostic(&self, diagnostic: Diagnostic) {
+        self.inner.send(
+            None,
+            Message::Diagnostic {
+                id: self.id,
+                diagnostic,
+                timestamp: timestamp(),
+            },
+        );
+    }
+
+    /// Report a test suite or case that started or finished
+    fn test(&self, event: TestEvent) {
+        let (id, timestamp) = (self.id, timestamp());
+        let message = match event {
+            TestEvent::Suite(suite) => Message::TestSuite {
+                id,
+                suite,
+                timestamp,
+            },
+            TestEvent::Case(case) => Message::TestCase {
+                id,
+                case,
+                timestamp,
+            },
+        };
+        self.inner.send(None, message);
+    }
+
     /// Finish task with whether it was successfull or not
     pub fn finish(&self, success: bool) {
-        tracing::trace!("Finishing {:?}: success: {success}", self.task);
+        tracing::trace!("Finishing {:?} {}: success: {success}", self.task, self.id);
 
-        self.inner
-            .tx
-            .send((
-                None,
-                Message::FinishCurrentTask {
-                    status: if success {
-                        TaskStatus::Succeeded
-                    } else {
-                        TaskStatus::Failed
-                    },
-                },
-            ))
-            .ok();
+        self.inner.finish_task(self.id, success);
 
         if !success {
             self.inner.open_logger();
         }
     }
 
-    pub fn consume(&self, mut process: Box<dyn ProcessExt + Send>) -> Result<Receiver<bool>> {
+    pub fn consume(
+        &self,
+        mut process: Box<dyn ProcessExt + Send>,
+    ) -> Result<Receiver<TaskOutcome>> {
         let mut stream = process.spawn_and_stream()?;
-        let cancel = self.inner.abort.clone();
+        let cancel = self.inner.cancel.clone();
+        let cancel_task = Arc::new(Notify::new());
+        self.inner
+            .cancels
+            .lock()
+            .unwrap()
+            .insert(self.id, cancel_task.clone());
         let abort = process.aborter().unwrap();
         let this = self.clone();
         let (send_status, recv_status) = channel(1);
+        let mut progress = Progress::new(self.steps);
+        let mut diagnostics = DiagnosticCollector::default();
+        let mut tests = (self.task == TaskKind::Test).then(TestParser::default);
+        let mut stderr = VecDeque::with_capacity(STDERR_TAIL_LINES);
 
         tokio::spawn(async move {
+            let canceled = async {
+                tokio::select! {
+                    _ = cancel.notified() => {},
+                    _ = cancel_task.notified() => {},
+                }
+            };
+            tokio::pin!(canceled);
             loop {
                 let send_status = send_status.clone();
                 tokio::select! {
-                    _ = cancel.notified() => {
+                    _ = &mut canceled => {
                         abort.notify_one();
-                        send_status.send(false).await.unwrap_or_default();
+                        this.inner.cancel_task(this.id);
+                        send_status.send(TaskOutcome::default()).await.unwrap_or_default();
                         break;
                     },
                     result = stream.next() => match result {
                         Some(output) => {
                             if let Some(succ) = output.is_success() {
                                 tracing::trace!("{output:?}");
+                                if let Some(diagnostic) = diagnostics.finish() {
+                                    this.diagnostic(diagnostic);
+                                }
+                                if let Some(report) = &this.report {
+                                    if let Err(err) = report.write(diagnostics.reported(), succ) {
+                                        this.warn(format!("Failed to write build report: {err}"));
+                                    }
+                                }
                                 this.finish(succ);
-                                send_status.send(succ).await.ok();
+                                let outcome = TaskOutcome {
+                                    success: succ,
+                                    exit_code: match &output {
+                                        ProcessItem::Exit(code) => code.parse().ok(),
+                                        _ => None,
+                                    },
+                                    stderr: stderr.into(),
+                                };
+                                send_status.send(outcome).await.ok();
                                 break;
-                            } else if let ProcessItem::Error(content) = output {
-                                this.error(content)
-                            } else if let ProcessItem::Output(content) = output {
-                                if content.to_lowercase().contains("error") {
-                                    this.error(content)
-                                } else if content.to_lowercase().contains("warn") {
-                                    this.warn(content)
-                                } else {
-                                    if content != "Resolving Packages" {
-                                        this.info(content)
-                                    };
+                            } else if let ProcessItem::Output(content)
+                                | ProcessItem::Error(content) = &output
+                            {
+                                if let ProcessItem::Error(content) = &output {
+                                    if stderr.len() == STDERR_TAIL_LINES {
+                                        stderr.pop_front();
+                                    }
+                                    stderr.push_back(content.clone());
+                                }
+                                if let Some((completed, total)) = progress.step(content) {
+                                    this.progress(completed, total);
+                                }
+                                if let Some(event) = tests.as_mut().and_then(|t| t.step(content)) {
+                                    this.test(event);
+                                }
+                                let level = match Diagnostic::parse(content) {
+                                    Some(diagnostic) => {
+                                        let level = diagnostic.severity.level();
+                                        if let Some(diagnostic) = diagnostics.push(diagnostic) {
+                                            this.diagnostic(diagnostic);
+                                        }
+                                        level
+                                    }
+                                    None if matches!(output, ProcessItem::Error(_)) => {
+                                        ContentLevel::Error
+                                    }
+                                    None => output_level(content),
+                                };
+                                if content != "Resolving Packages" {
+                                    this.update(level, content)
                                 }
                             }
                         }
@@ -124,6 +259,7 @@ impl Task {
                     }
                 };
             }
+            this.inner.cancels.lock().unwrap().remove(&this.id);
         });
         Ok(recv_status)
     }
@@ -132,3 +268,36 @@ impl Task {
         self.inner.as_ref()
     }
 }
+
+impl ActiveTasks {
+    /// Track a task that started
+    pub(crate) fn start(&self, task: TaskInfo) {
+        self.0.lock().unwrap().push(task);
+    }
+
+    /// Stop tracking a task, returns false if it wasn't under progress
+    pub(crate) fn finish(&self, id: u32) -> bool {
+        let mut tasks = self.0.lock().unwrap();
+        let len = tasks.len();
+        tasks.retain(|task| task.id != id);
+        tasks.len() != len
+    }
+
+    /// Whether a given task is under progress
+    pub fn contains(&self, id: u32) -> bool {
+        self.0.lock().unwrap().iter().any(|task| task.id == id)
+    }
+
+    /// Get tasks under progress, oldest first
+    pub fn list(&self) -> Vec<TaskInfo> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// Milliseconds since unix epoch, as carried by task messages
+pub(crate) fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,263 @@
+use super::{Diagnostic, DiagnosticSeverity};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use typescript_type_def::TypeDef;
+
+/// Test case started or finished
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct TestCase {
+    /// Test class, without module name
+    pub suite: String,
+    /// Test method, e.g. testExample
+    pub name: String,
+    pub status: TestStatus,
+    /// Duration in milliseconds, once finished
+    pub duration: Option<u64>,
+    /// Failed assertions of the test case
+    pub failures: Vec<TestFailure>,
+}
+
+/// Test suite started or finished, e.g. a test class or bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct TestSuite {
+    pub name: String,
+    pub status: TestStatus,
+    /// Number of test cases executed, once finished
+    pub tests: u32,
+    /// Number of test cases failed, once finished
+    pub failures: u32,
+    /// Duration in milliseconds, once finished
+    pub duration: Option<u64>,
+}
+
+/// Failed assertion of a test case
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct TestFailure {
+    pub file: PathBuf,
+    pub line: u32,
+    pub message: String,
+}
+
+/// Status of a test case or suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub enum TestStatus {
+    Started,
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Test case or suite parsed from test output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TestEvent {
+    Case(TestCase),
+    Suite(TestSuite),
+}
+
+/// Parse XCTest output of `swift test` and `xcodebuild test`, on macOS and Linux, e.g.
+/// `Test Case '-[AppTests.AppTests testExample]' passed (0.001 seconds).` or
+/// `Test Case 'AppTests.testExample' started at 2022-10-18 10:00:00.000`.
+#[derive(Debug, Default)]
+pub(crate) struct TestParser {
+    /// Failures of the test case under progress
+    failures: Vec<TestFailure>,
+    /// Finished suite waiting for its `Executed n tests` summary line
+    suite: Option<(String, TestStatus)>,
+}
+
+impl TestParser {
+    /// Parse an output line, returns a test case or suite if it started or finished
+    pub fn step(&mut self, line: &str) -> Option<TestEvent> {
+        let line = line.trim();
+        if let Some((name, rest)) = quoted(line, "Test Case") {
+            let (suite, name) = case_name(name)?;
+            let status = status(rest)?;
+            let failures = match status {
+                TestStatus::Started => {
+                    self.failures.clear();
+                    vec![]
+                }
+                _ => std::mem::take(&mut self.failures),
+            };
+            return Some(TestEvent::Case(TestCase {
+                suite,
+                name,
+                status,
+                duration: rest.split_once('(').and_then(|(_, d)| seconds(d)),
+                failures,
+            }));
+        }
+
+        if let Some((name, rest)) = quoted(line, "Test Suite") {
+            let status = status(rest)?;
+            if status != TestStatus::Started {
+                self.suite = Some((name.into(), status));
+                return None;
+            }
+            return Some(TestEvent::Suite(TestSuite {
+                name: name.into(),
+                status,
+                tests: 0,
+                failures: 0,
+                duration: None,
+            }));
+        }
+
+        if let Some(summary) = line.strip_prefix("Executed ") {
+            let (name, status) = self.suite.take()?;
+            let count = |prefix: &str| {
+                let (count, _) = summary.split_once(prefix)?.1.split_once(' ')?;
+                count.parse::<u32>().ok()
+            };
+            return Some(TestEvent::Suite(TestSuite {
+                name,
+                status,
+                tests: summary.split_once(' ')?.0.parse().ok()?,
+                failures: count(", with ").unwrap_or_default(),
+                duration: summary.rsplit_once(" in ").and_then(|(_, d)| seconds(d)),
+            }));
+        }
+
+        if let Some(diagnostic) = Diagnostic::parse(line) {
+            if diagnostic.severity == DiagnosticSeverity::Error {
+                let message = match diagnostic.message.split_once(" : ") {
+                    Some((_, message)) => message.into(),
+                    None => diagnostic.message,
+                };
+                self.failures.push(TestFailure {
+                    file: diagnostic.file,
+                    line: diagnostic.line,
+                    message,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// Split `<prefix> '<name>' <rest>`
+fn quoted<'a>(line: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let rest = line.strip_prefix(prefix)?.trim_start().strip_prefix('\'')?;
+    rest.split_once("' ")
+}
+
+/// Split `-[Module.Class testName]` or `Class.testName` into class and test name
+fn case_name(name: &str) -> Option<(String, String)> {
+    let (class, test) = match name.strip_prefix("-[").and_then(|n| n.strip_suffix(']')) {
+        Some(name) => name.split_once(' ')?,
+        None => name.rsplit_once('.')?,
+    };
+    let class = class.rsplit('.').next()?;
+    Some((class.into(), test.into()))
+}
+
+fn status(rest: &str) -> Option<TestStatus> {
+    match rest.split_whitespace().next()? {
+        "started" => Some(TestStatus::Started),
+        "passed" => Some(TestStatus::Passed),
+        "failed" => Some(TestStatus::Failed),
+        "skipped" => Some(TestStatus::Skipped),
+        _ => None,
+    }
+}
+
+/// Parse leading seconds, e.g. `0.001 seconds)`, into milliseconds
+fn seconds(text: &str) -> Option<u64> {
+    let seconds = text.split_whitespace().next()?.parse::<f64>().ok()?;
+    Some((seconds * 1000.0).round() as u64)
+}
+
+#[test]
+fn test_parse_xctest_macos() {
+    let mut parser = TestParser::default();
+    let mut events = [
+        "Test Suite 'AppTests' started at 2022-10-18 10:00:00.000",
+        "Test Case '-[AppTests.AppTests testAdd]' started.",
+        "Test Case '-[AppTests.AppTests testAdd]' passed (0.001 seconds).",
+        "Test Case '-[AppTests.AppTests testSub]' started.",
+        "/tmp/App/Tests/AppTests.swift:12: error: -[AppTests.AppTests testSub] : XCTAssertEqual failed: (\"1\") is not equal to (\"2\")",
+        "Test Case '-[AppTests.AppTests testSub]' failed (0.125 seconds).",
+        "Test Suite 'AppTests' failed at 2022-10-18 10:00:00.200.",
+        "\t Executed 2 tests, with 1 failure (0 unexpected) in 0.126 (0.130) seconds",
+    ]
+    .iter()
+    .filter_map(|line| parser.step(line));
+
+    assert!(matches!(events.next(), Some(TestEvent::Suite(s)) if s.status == TestStatus::Started));
+    assert!(matches!(events.next(), Some(TestEvent::Case(c)) if c.status == TestStatus::Started));
+    assert_eq!(
+        events.next(),
+        Some(TestEvent::Case(TestCase {
+            suite: "AppTests".into(),
+            name: "testAdd".into(),
+            status: TestStatus::Passed,
+            duration: Some(1),
+            failures: vec![],
+        }))
+    );
+    events.next();
+    let case = match events.next() {
+        Some(TestEvent::Case(case)) => case,
+        event => panic!("expected test case, got {event:?}"),
+    };
+    assert_eq!(case.status, TestStatus::Failed);
+    assert_eq!(case.duration, Some(125));
+    assert_eq!(case.failures[0].line, 12);
+    assert!(case.failures[0]
+        .message
+        .starts_with("XCTAssertEqual failed"));
+    assert_eq!(
+        events.next(),
+        Some(TestEvent::Suite(TestSuite {
+            name: "AppTests".into(),
+            status: TestStatus::Failed,
+            tests: 2,
+            failures: 1,
+            duration: Some(126),
+        }))
+    );
+    assert_eq!(events.next(), None);
+}
+
+#[test]
+fn test_parse_xctest_linux() {
+    let mut parser = TestParser::default();
+    let events = [
+        "Test Suite 'All tests' started at 2022-10-18 10:00:00.000",
+        "Test Case 'AppTests.testAdd' started at 2022-10-18 10:00:00.001",
+        "Test Case 'AppTests.testAdd' passed (0.002 seconds)",
+        "Test Case 'AppTests.testSkip' skipped (0.0 seconds)",
+        "Test Suite 'All tests' passed at 2022-10-18 10:00:00.003",
+        "\t Executed 2 tests, with 0 failures (0 unexpected) in 0.002 (0.002) seconds",
+    ]
+    .iter()
+    .filter_map(|line| parser.step(line))
+    .collect::<Vec<_>>();
+
+    let cases = events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::Case(case) => Some(case),
+            TestEvent::Suite(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let suites = events
+        .iter()
+        .filter_map(|event| match event {
+            TestEvent::Suite(suite) => Some(suite),
+            TestEvent::Case(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(cases.len(), 3);
+    assert_eq!(
+        (cases[1].suite.as_str(), cases[1].name.as_str()),
+        ("AppTests", "testAdd")
+    );
+    assert_eq!(cases[1].duration, Some(2));
+    assert_eq!(cases[2].status, TestStatus::Skipped);
+    assert_eq!(suites.len(), 2);
+    assert_eq!((suites[1].tests, suites[1].failures), (2, 0));
+    assert_eq!(suites[1].status, TestStatus::Passed);
+}
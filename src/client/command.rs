@@ -0,0 +1,354 @@
+use super::printer::{print_message, print_status};
+use super::{Client, Listener};
+use crate::server::{
+    BuildRequest, DropRequest, RequestKind, RunRequest, StatusRequest, TestRequest,
+};
+use crate::{BuildSettings, DeviceLookup, Error, Operation, Result};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: xbase [COMMAND] [OPTIONS]
+
+Without a command, xbase runs the daemon.
+
+Commands:
+  build     Build a target and wait for the build to finish
+  run       Build and run a target, streaming its output
+  test      Build and run tests of a test target, printing their results
+  watch     Rebuild (or rerun with --run) a target on file changes
+  stop      Stop watching a target (or its runner with --run)
+  status    Print registered projects and their state
+  logs      Print daemon logs, or follow project messages with -f
+
+Options:
+  --root <PATH>             Project root, defaults to current directory
+  --target <NAME>           Target to build or run
+  --configuration <NAME>    Build configuration, defaults to Debug
+  --scheme <NAME>           Scheme to build with
+  --device <ID>             Device/simulator id to run on
+  --run                     Watch/stop the runner instead of the build
+  --filter <TEST>           Only run tests matching Class or Class/testName
+  --sarif <PATH>            Write a SARIF report of build diagnostics
+  --junit <PATH>            Write a JUnit report of the build outcome
+  -f, --follow              Follow project messages
+  -h, --help                Print this message";
+
+/// How long to wait for the project state after registering
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client subcommands
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    Build,
+    Run,
+    Test,
+    Watch,
+    Stop,
+    Status,
+    Logs,
+}
+
+/// Parsed command line arguments
+#[derive(Debug, Default)]
+struct Args {
+    root: Option<PathBuf>,
+    target: Option<String>,
+    configuration: Option<String>,
+    scheme: Option<String>,
+    device: Option<String>,
+    filter: Option<String>,
+    run: bool,
+    follow: bool,
+    sarif: Option<PathBuf>,
+    junit: Option<PathBuf>,
+}
+
+/// Run client command with the given arguments (without the binary name), returns exit code
+pub async fn run(args: Vec<String>) -> i32 {
+    let (command, args) = match parse(args) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            println!("{USAGE}");
+            return 0;
+        }
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return 2;
+        }
+    };
+
+    let result = match command {
+        Command::Build => build(args).await,
+        Command::Run => run_target(args).await,
+        Command::Test => test(args).await,
+        Command::Watch => watch(args).await,
+        Command::Stop => stop(args).await,
+        Command::Status => status().await,
+        Command::Logs => logs(args).await,
+    };
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+fn parse(args: Vec<String>) -> Result<Option<(Command, Args)>, String> {
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        Some("build") => Command::Build,
+        Some("run") => Command::Run,
+        Some("test") => Command::Test,
+        Some("watch") => Command::Watch,
+        Some("stop") => Command::Stop,
+        Some("status") => Command::Status,
+        Some("logs") => Command::Logs,
+        Some("-h" | "--help" | "help") | None => return Ok(None),
+        Some(command) => return Err(format!("Unknown command `{command}`")),
+    };
+
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for `{arg}`"))
+        };
+        match arg.as_str() {
+            "--root" => parsed.root = Some(value()?.into()),
+            "--target" => parsed.target = Some(value()?),
+            "--configuration" => parsed.configuration = Some(value()?),
+            "--scheme" => parsed.scheme = Some(value()?),
+            "--device" => parsed.device = Some(value()?),
+            "--filter" => parsed.filter = Some(value()?),
+            "--run" => parsed.run = true,
+            "--sarif" => parsed.sarif = Some(value()?.into()),
+            "--junit" => parsed.junit = Some(value()?.into()),
+            "-f" | "--follow" => parsed.follow = true,
+            "-h" | "--help" => return Ok(None),
+            arg => return Err(format!("Unknown argument `{arg}`")),
+        }
+    }
+
+    Ok(Some((command, parsed)))
+}
+
+impl Args {
+    fn root(&self) -> Result<PathBuf> {
+        match &self.root {
+            Some(root) => Ok(root.canonicalize()?),
+            None => Ok(std::env::current_dir()?),
+        }
+    }
+
+    fn settings(&self) -> Result<BuildSettings> {
+        Ok(BuildSettings {
+            target: self
+                .target
+                .clone()
+                .ok_or_else(|| Error::Unexpected("Missing --target".into()))?,
+            configuration: self.configuration.clone().unwrap_or_else(|| "Debug".into()),
+            scheme: self.scheme.clone(),
+            sarif: self.report_path(&self.sarif)?,
+            junit: self.report_path(&self.junit)?,
+        })
+    }
+
+    /// Resolve report path relative to current directory, as the daemon resolves it to root
+    fn report_path(&self, path: &Option<PathBuf>) -> Result<Option<PathBuf>> {
+        match path {
+            Some(path) => Ok(Some(std::env::current_dir()?.join(path))),
+            None => Ok(None),
+        }
+    }
+
+    fn device(&self) -> Option<DeviceLookup> {
+        let id = self.device.clone()?;
+        Some(DeviceLookup::new(id.clone(), id))
+    }
+
+    fn request(&self, run: bool, operation: Operation) -> Result<RequestKind> {
+        let (root, settings) = (self.root()?, self.settings()?);
+        Ok(if run {
+            RequestKind::Run(RunRequest {
+                root,
+                settings,
+                device: self.device(),
+                operation,
+            })
+        } else {
+            RequestKind::Build(BuildRequest {
+                root,
+                settings,
+                operation,
+            })
+        })
+    }
+}
+
+/// Register project root and wait until the daemon sent project state
+async fn register(client: &mut Client, root: PathBuf) -> Result<Listener> {
+    let response = client.register(root).await?;
+    let mut listener = Listener::connect(&response).await?;
+
+    let registered = async {
+        while let Some(message) = listener.next().await {
+            print_message(&message);
+            if message["type"] == "SetState" && message["args"]["key"] == "projectInfo" {
+                break;
+            }
+        }
+    };
+    tokio::time::timeout(REGISTER_TIMEOUT, registered)
+        .await
+        .ok();
+
+    Ok(listener)
+}
+
+/// Unregister project root
+async fn unregister(client: &mut Client, root: PathBuf) -> Result<()> {
+    let request = DropRequest {
+        id: 0,
+        roots: vec![root],
+    };
+    client.request(RequestKind::Drop(request)).await
+}
+
+/// Print messages until a task of the given kind finishes, returns whether it succeeded.
+///
+/// Errors notified before the task started (e.g. failing to start the runner) are treated
+/// as failure.
+async fn wait_task(listener: &mut Listener, kind: &str) -> bool {
+    let mut task = Value::Null;
+
+    while let Some(message) = listener.next().await {
+        print_message(&message);
+        let args = &message["args"];
+        match message["type"].as_str().unwrap_or_default() {
+            "SetCurrentTask" if task.is_null() && args["kind"] == kind => {
+                task = args["id"].clone();
+            }
+            "FinishCurrentTask" if args["id"] == task => return args["status"] == "Succeeded",
+            "Notify" if task.is_null() && args["level"] == "Error" => return false,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Print messages until interrupted or the broadcaster is closed
+async fn follow(listener: &mut Listener) {
+    let follow = async {
+        while let Some(message) = listener.next().await {
+            print_message(&message);
+        }
+    };
+
+    tokio::select! {
+        _ = follow => {},
+        _ = tokio::signal::ctrl_c() => {},
+    }
+}
+
+async fn build(args: Args) -> Result<bool> {
+    let root = args.root()?;
+    let mut client = Client::connect().await?;
+    let mut listener = register(&mut client, root.clone()).await?;
+
+    client
+        .request(args.request(false, Operation::Once)?)
+        .await?;
+    let success = wait_task(&mut listener, "Build").await;
+
+    unregister(&mut client, root).await?;
+    Ok(success)
+}
+
+async fn run_target(args: Args) -> Result<bool> {
+    let root = args.root()?;
+    let mut client = Client::connect().await?;
+    let mut listener = register(&mut client, root.clone()).await?;
+
+    client.request(args.request(true, Operation::Once)?).await?;
+    let success = wait_task(&mut listener, "Run").await;
+    if success {
+        follow(&mut listener).await;
+    }
+
+    unregister(&mut client, root).await?;
+    Ok(success)
+}
+
+async fn test(args: Args) -> Result<bool> {
+    let root = args.root()?;
+    let mut client = Client::connect().await?;
+    let mut listener = register(&mut client, root.clone()).await?;
+
+    let request = TestRequest {
+        root: root.clone(),
+        settings: args.settings()?,
+        filter: args.filter.clone(),
+        device: args.device(),
+    };
+    client.request(RequestKind::Test(request)).await?;
+    let success = wait_task(&mut listener, "Test").await;
+
+    unregister(&mut client, root).await?;
+    Ok(success)
+}
+
+async fn watch(args: Args) -> Result<bool> {
+    let root = args.root()?;
+    let mut client = Client::connect().await?;
+    let mut listener = register(&mut client, root.clone()).await?;
+
+    client
+        .request(args.request(args.run, Operation::Watch)?)
+        .await?;
+    follow(&mut listener).await;
+    client
+        .request(args.request(args.run, Operation::Stop)?)
+        .await?;
+
+    unregister(&mut client, root).await?;
+    Ok(true)
+}
+
+async fn stop(args: Args) -> Result<bool> {
+    let mut client = Client::connect().await?;
+    client
+        .request(args.request(args.run, Operation::Stop)?)
+        .await?;
+    Ok(true)
+}
+
+async fn status() -> Result<bool> {
+    let mut client = Client::connect().await?;
+    let request = StatusRequest { root: None };
+    let status: Value = client.request(RequestKind::Status(request)).await?;
+    print_status(&status);
+    Ok(true)
+}
+
+async fn logs(args: Args) -> Result<bool> {
+    if !args.follow {
+        let log_path = crate::config::get().log_path.clone();
+        print!("{}", tokio::fs::read_to_string(log_path).await?);
+        return Ok(true);
+    }
+
+    let root = args.root()?;
+    let mut client = Client::connect().await?;
+    let mut listener = register(&mut client, root.clone()).await?;
+    follow(&mut listener).await;
+
+    unregister(&mut client, root).await?;
+    Ok(true)
+}
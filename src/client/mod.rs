@@ -0,0 +1,158 @@
+//! Command line client speaking the daemon [`Request`] protocol
+mod command;
+mod printer;
+
+pub use command::run;
+
+use crate::server::{ClientInfo, RegisterRequest, RegisterResponse, Request, RequestKind};
+use crate::server::{Response, MESSAGE_KINDS, PROTOCOL_VERSION};
+use crate::{Error, Result};
+use futures::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec};
+
+/// How long to wait for an auto-started daemon to accept connections
+const DAEMON_START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connection to the daemon main socket
+pub struct Client {
+    reader: FramedRead<OwnedReadHalf, LinesCodec>,
+    writer: FramedWrite<OwnedWriteHalf, LinesCodec>,
+    next_id: u32,
+}
+
+/// Connection to a project broadcaster
+pub struct Listener {
+    lines: Lines<BufReader<UnixStream>>,
+}
+
+impl Client {
+    /// Connect to the daemon, starting it if it isn't running
+    pub async fn connect() -> Result<Self> {
+        let sock_addr = crate::config::get().sock_addr.clone();
+        let stream = match UnixStream::connect(&sock_addr).await {
+            Ok(stream) => stream,
+            Err(_) => Self::start_daemon(&sock_addr).await?,
+        };
+
+        Ok(Self::new(stream))
+    }
+
+    /// Connect to a daemon listening at the given address, without starting it
+    pub async fn connect_to(sock_addr: &Path) -> Result<Self> {
+        Ok(Self::new(UnixStream::connect(sock_addr).await?))
+    }
+
+    fn new(stream: UnixStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self {
+            reader: FramedRead::new(reader, LinesCodec::new()),
+            writer: FramedWrite::new(writer, LinesCodec::new()),
+            next_id: 0,
+        }
+    }
+
+    /// Spawn daemon in the background and wait for it to accept connections
+    async fn start_daemon(sock_addr: &Path) -> Result<UnixStream> {
+        eprintln!("Starting xbase daemon");
+        // Own process group, so that Ctrl-C in the cli doesn't reach the daemon. It logs to its
+        // log file, so standard streams aren't needed.
+        std::process::Command::new(std::env::current_exe()?)
+            .process_group(0)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let connect = async {
+            loop {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                if let Ok(stream) = UnixStream::connect(sock_addr).await {
+                    return stream;
+                }
+            }
+        };
+
+        tokio::time::timeout(DAEMON_START_TIMEOUT, connect)
+            .await
+            .map_err(|_| Error::Unexpected(format!("Daemon didn't start at {sock_addr:?}")))
+    }
+
+    /// Send a request and wait for its response
+    pub async fn request<T: DeserializeOwned>(&mut self, kind: RequestKind) -> Result<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let request = serde_json::to_string(&Request { id: Some(id), kind })?;
+        self.writer
+            .send(request)
+            .await
+            .map_err(|e| Error::SendError(e.to_string()))?;
+
+        while let Some(line) = self.reader.next().await {
+            let line = line.map_err(|e| Error::Unexpected(e.to_string()))?;
+            let response = serde_json::from_str::<Response>(&line)?;
+            if response.id() == Some(id) {
+                return response.into_result();
+            }
+        }
+
+        Err(Error::Unexpected("Daemon closed the connection".into()))
+    }
+
+    /// Register project root
+    pub async fn register(&mut self, root: PathBuf) -> Result<RegisterResponse> {
+        let client = ClientInfo {
+            name: "cli".into(),
+            protocol: PROTOCOL_VERSION,
+            messages: MESSAGE_KINDS.iter().map(ToString::to_string).collect(),
+        };
+        let request = RegisterRequest {
+            id: 0,
+            root,
+            client: Some(client),
+            token: None,
+            session: None,
+        };
+        self.request(RequestKind::Register(request)).await
+    }
+}
+
+impl Listener {
+    /// Connect to the project broadcaster of a registration
+    pub async fn connect(registration: &RegisterResponse) -> Result<Self> {
+        let RegisterResponse {
+            id,
+            address,
+            broadcast_token,
+            ..
+        } = registration;
+        let mut stream = UnixStream::connect(address).await?;
+        stream
+            .write_all(format!("{id} {broadcast_token}\n").as_bytes())
+            .await?;
+
+        Ok(Self {
+            lines: BufReader::new(stream).lines(),
+        })
+    }
+
+    /// Read next broadcast message, None if the broadcaster is closed
+    pub async fn next(&mut self) -> Option<Value> {
+        while let Some(line) = self.lines.next_line().await.ok()? {
+            match serde_json::from_str(&line) {
+                Ok(message) => return Some(message),
+                Err(err) => tracing::warn!("Failed to parse message {line}: {err}"),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,120 @@
+use serde_json::Value;
+
+/// Print a broadcast message in a human readable form
+pub fn print_message(message: &Value) {
+    let args = &message["args"];
+    let str = |key: &str| args[key].as_str().unwrap_or_default();
+
+    match message["type"].as_str().unwrap_or_default() {
+        "Notify" | "Log" if str("level") == "Trace" => {}
+        "Notify" | "Log" => print_content(str("level"), str("content")),
+        "SetCurrentTask" => println!("==> {} {}", str("kind"), str("target")),
+        "UpdateCurrentTask" => print_content(str("level"), str("content")),
+        "TestCase" => print_test_case(&args["case"]),
+        "TestSuite" => print_test_suite(&args["suite"]),
+        "FinishCurrentTask" => println!("==> {}", str("status")),
+        "SetWatching" => {
+            let target = args["settings"]["target"].as_str().unwrap_or_default();
+            match args["watching"].as_bool().unwrap_or_default() {
+                true => println!("==> Watching {target}"),
+                false => println!("==> Stopped watching {target}"),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Print a finished test case with its failures
+fn print_test_case(case: &Value) {
+    let str = |key: &str| case[key].as_str().unwrap_or_default();
+    if str("status") == "Started" {
+        return;
+    }
+    let duration = case["duration"].as_u64().unwrap_or_default();
+    println!(
+        "    {} {}.{} ({duration}ms)",
+        str("status"),
+        str("suite"),
+        str("name")
+    );
+    for failure in case["failures"].as_array().into_iter().flatten() {
+        let file = failure["file"].as_str().unwrap_or_default();
+        let message = failure["message"].as_str().unwrap_or_default();
+        println!("      {file}:{}: {message}", failure["line"]);
+    }
+}
+
+/// Print a finished test suite summary
+fn print_test_suite(suite: &Value) {
+    let str = |key: &str| suite[key].as_str().unwrap_or_default();
+    if str("status") == "Started" {
+        return;
+    }
+    println!(
+        "==> {} {}: {} tests, {} failures",
+        str("name"),
+        str("status"),
+        suite["tests"],
+        suite["failures"]
+    );
+}
+
+/// Print daemon status, as returned by Status request
+pub fn print_status(status: &Value) {
+    let runtimes = status.as_array().cloned().unwrap_or_default();
+    if runtimes.is_empty() {
+        println!("No registered projects");
+    }
+
+    for runtime in runtimes.iter() {
+        let info = &runtime["runtime"];
+        let kind = info["kind"].as_str().unwrap_or("Unresponsive");
+        println!("{} ({kind})", runtime["name"].as_str().unwrap_or_default());
+        println!(
+            "  root:     {}",
+            runtime["root"].as_str().unwrap_or_default()
+        );
+        println!("  clients:  {}", join(&runtime["clients"]));
+        println!("  watching: {}", join(&info["watchlist"]));
+        for task in info["tasks"].as_array().into_iter().flatten() {
+            let kind = task["kind"].as_str().unwrap_or_default();
+            let target = task["target"].as_str().unwrap_or_default();
+            println!("  task {}:   {kind} {target}", task["id"]);
+        }
+        for stats in info["listeners"].as_array().into_iter().flatten() {
+            println!(
+                "  client {}: {} sent, {} queued, {} dropped",
+                stats["id"], stats["sent"], stats["queued"], stats["dropped"]
+            );
+        }
+    }
+}
+
+fn print_content(level: &str, content: &str) {
+    match level {
+        "Error" | "Warn" => eprintln!("[{level}] {content}"),
+        _ => println!("{content}"),
+    }
+}
+
+fn join(value: &Value) -> String {
+    let values = value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map(String::from)
+                        .unwrap_or_else(|| v.to_string())
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if values.is_empty() {
+        "-".into()
+    } else {
+        values.join(", ")
+    }
+}
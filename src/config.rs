@@ -0,0 +1,309 @@
+//! Daemon configuration
+//!
+//! Paths are resolved in the following order:
+//!
+//! 1. Environment variables (e.g. `XBASE_SOCK_ADDR`)
+//! 2. Config file at `$XBASE_CONFIG` or `$XDG_CONFIG_HOME/xbase/daemon.json`
+//! 3. `$XDG_RUNTIME_DIR/xbase`
+//! 4. `$TMPDIR/xbase-$UID`
+use crate::{Error, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::Level;
+
+/// Environment variable to override config file path
+pub const CONFIG_PATH_ENV: &str = "XBASE_CONFIG";
+/// Environment variable to override runtime directory
+pub const RUNTIME_DIR_ENV: &str = "XBASE_RUNTIME_DIR";
+/// Environment variable to override daemon socket address
+pub const SOCK_ADDR_ENV: &str = "XBASE_SOCK_ADDR";
+/// Environment variable to override daemon pid file path
+pub const PID_PATH_ENV: &str = "XBASE_PID_PATH";
+/// Environment variable to override daemon log file path
+pub const LOG_PATH_ENV: &str = "XBASE_LOG_PATH";
+/// Environment variable to override daemon state file path
+pub const STATE_PATH_ENV: &str = "XBASE_STATE_PATH";
+/// Environment variable to override broadcast sockets directory
+pub const BROADCAST_ROOT_ENV: &str = "XBASE_BROADCAST_ROOT";
+/// Environment variable to set tcp address to listen on, e.g. 0.0.0.0:6969
+pub const TCP_ADDR_ENV: &str = "XBASE_TCP_ADDR";
+/// Environment variable to set shared secret tcp clients must register with
+pub const TCP_TOKEN_ENV: &str = "XBASE_TCP_TOKEN";
+/// Environment variable to set seconds after which an idle daemon exits, 0 to disable
+pub const IDLE_TIMEOUT_ENV: &str = "XBASE_IDLE_TIMEOUT";
+/// Environment variable to set daemon log level, e.g. info
+pub const LOG_LEVEL_ENV: &str = "XBASE_LOG_LEVEL";
+/// Environment variable to set how many messages are queued for each broadcast client
+pub const BROADCAST_QUEUE_SIZE_ENV: &str = "XBASE_BROADCAST_QUEUE_SIZE";
+/// Environment variable to set what happens when a broadcast client queue is full
+pub const BROADCAST_OVERFLOW_ENV: &str = "XBASE_BROADCAST_OVERFLOW";
+/// Environment variable to set a directory to write SARIF and JUnit build reports to
+pub const REPORT_DIR_ENV: &str = "XBASE_REPORT_DIR";
+
+/// Config file path relative to config home
+pub const CONFIG_FILE: &str = "xbase/daemon.json";
+/// Daemon socket file name
+pub const SOCK_NAME: &str = "xbase.socket";
+/// Daemon pid file name
+pub const PID_NAME: &str = "xbase.pid";
+/// Daemon log file name
+pub const LOG_NAME: &str = "xbase.log";
+/// Daemon state file name
+pub const STATE_NAME: &str = "state.json";
+/// Broadcast sockets directory name
+pub const BROADCAST_NAME: &str = "broadcast";
+/// Default number of messages queued for each broadcast client
+pub const BROADCAST_QUEUE_SIZE: usize = 1024;
+
+static CONFIG: Lazy<RwLock<Arc<Config>>> = Lazy::new(|| {
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{err}, using default configuration");
+        Config::resolve(ConfigFile::default(), env)
+    });
+    RwLock::new(Arc::new(config))
+});
+
+/// Get current configuration
+pub fn get() -> Arc<Config> {
+    CONFIG.read().unwrap().clone()
+}
+
+/// Replace current configuration
+pub fn set(config: Config) {
+    *CONFIG.write().unwrap() = Arc::new(config);
+}
+
+/// Config file content
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub runtime_dir: Option<PathBuf>,
+    pub sock_addr: Option<PathBuf>,
+    pub pid_path: Option<PathBuf>,
+    pub log_path: Option<PathBuf>,
+    pub state_path: Option<PathBuf>,
+    pub broadcast_root: Option<PathBuf>,
+    pub tcp_addr: Option<String>,
+    pub tcp_token: Option<String>,
+    pub idle_timeout: Option<u64>,
+    pub log_level: Option<String>,
+    pub broadcast_queue_size: Option<usize>,
+    pub broadcast_overflow: Option<OverflowPolicy>,
+    pub report_dir: Option<PathBuf>,
+}
+
+/// What to do with a broadcast client whose message queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued message to make room for the new one
+    #[default]
+    DropOldest,
+    /// Disconnect the client
+    Disconnect,
+}
+
+/// Resolved daemon configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Directory holding daemon runtime files
+    pub runtime_dir: PathBuf,
+    /// Daemon socket address
+    pub sock_addr: PathBuf,
+    /// Daemon pid file path
+    pub pid_path: PathBuf,
+    /// Daemon log file path
+    pub log_path: PathBuf,
+    /// Daemon state file path, used to restore projects watchers after restart
+    pub state_path: PathBuf,
+    /// Directory holding project broadcast sockets
+    pub broadcast_root: PathBuf,
+    /// Tcp address to listen on for remote clients
+    pub tcp_addr: Option<String>,
+    /// Shared secret remote clients must provide
+    pub tcp_token: Option<String>,
+    /// How long the daemon keeps running without clients and projects
+    pub idle_timeout: Option<Duration>,
+    /// Default log level, RUST_LOG takes precedence
+    pub log_level: Level,
+    /// Maximum number of messages queued for each broadcast client
+    pub broadcast_queue_size: usize,
+    /// What to do with a broadcast client whose message queue is full
+    pub broadcast_overflow: OverflowPolicy,
+    /// Directory to write build reports of all projects to, under a directory per project, when
+    /// builds don't set report paths
+    pub report_dir: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Get config file path
+    pub fn path() -> PathBuf {
+        if let Some(path) = env(CONFIG_PATH_ENV) {
+            return path.into();
+        }
+        env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env("HOME").unwrap_or_default()).join(".config"))
+            .join(CONFIG_FILE)
+    }
+
+    /// Read config file, or default if it doesn't exist
+    pub fn read() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| Error::Unexpected(format!("Failed to parse {path:?}: {e}")))
+    }
+}
+
+impl Config {
+    /// Load configuration from environment variables and config file
+    pub fn load() -> Result<Self> {
+        ConfigFile::read().map(|file| Self::resolve(file, env))
+    }
+
+    /// Resolve configuration from config file and environment variables getter
+    pub fn resolve(file: ConfigFile, env: impl Fn(&str) -> Option<String>) -> Self {
+        let path = |key: &str| env(key).map(PathBuf::from);
+
+        let runtime_dir = path(RUNTIME_DIR_ENV)
+            .or(file.runtime_dir)
+            .or_else(|| path("XDG_RUNTIME_DIR").map(|p| p.join("xbase")))
+            .unwrap_or_else(|| {
+                let uid = unsafe { libc::getuid() };
+                std::env::temp_dir().join(format!("xbase-{uid}"))
+            });
+
+        Self {
+            sock_addr: path(SOCK_ADDR_ENV)
+                .or(file.sock_addr)
+                .unwrap_or_else(|| runtime_dir.join(SOCK_NAME)),
+            pid_path: path(PID_PATH_ENV)
+                .or(file.pid_path)
+                .unwrap_or_else(|| runtime_dir.join(PID_NAME)),
+            log_path: path(LOG_PATH_ENV)
+                .or(file.log_path)
+                .unwrap_or_else(|| runtime_dir.join(LOG_NAME)),
+            state_path: path(STATE_PATH_ENV)
+                .or(file.state_path)
+                .unwrap_or_else(|| runtime_dir.join(STATE_NAME)),
+            broadcast_root: path(BROADCAST_ROOT_ENV)
+                .or(file.broadcast_root)
+                .unwrap_or_else(|| runtime_dir.join(BROADCAST_NAME)),
+            tcp_addr: env(TCP_ADDR_ENV).or(file.tcp_addr),
+            tcp_token: env(TCP_TOKEN_ENV).or(file.tcp_token),
+            idle_timeout: env(IDLE_TIMEOUT_ENV)
+                .and_then(|v| v.parse().ok())
+                .or(file.idle_timeout)
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            log_level: env(LOG_LEVEL_ENV)
+                .or(file.log_level)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Level::DEBUG),
+            broadcast_queue_size: env(BROADCAST_QUEUE_SIZE_ENV)
+                .and_then(|v| v.parse().ok())
+                .or(file.broadcast_queue_size)
+                .filter(|size| *size > 0)
+                .unwrap_or(BROADCAST_QUEUE_SIZE),
+            broadcast_overflow: env(BROADCAST_OVERFLOW_ENV)
+                .and_then(|v| v.parse().ok())
+                .or(file.broadcast_overflow)
+                .unwrap_or_default(),
+            report_dir: path(REPORT_DIR_ENV).or(file.report_dir),
+            runtime_dir,
+        }
+    }
+
+    /// Check whether the given token matches the configured tcp token
+    pub fn verify_tcp_token(&self, token: Option<&str>) -> bool {
+        match (self.tcp_token.as_deref(), token) {
+            (Some(expected), Some(token)) => crate::util::token::token_matches(expected, token),
+            _ => false,
+        }
+    }
+
+    /// Create runtime directories only accessible by current user
+    pub fn ensure_runtime_dirs(&self) -> Result<()> {
+        let parents = [
+            Some(self.runtime_dir.as_path()),
+            Some(self.broadcast_root.as_path()),
+            self.sock_addr.parent(),
+            self.pid_path.parent(),
+            self.log_path.parent(),
+            self.state_path.parent(),
+        ];
+        for dir in parents.into_iter().flatten() {
+            create_private_dir(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Create directory and its parents with 0700 permissions if it doesn't exist
+fn create_private_dir(path: &Path) -> Result<()> {
+    if !path.exists() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(path)?;
+    }
+    Ok(())
+}
+
+/// Restrict file permissions to current user (0600)
+pub fn set_private_permissions(path: impl AsRef<Path>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let permissions = std::fs::Permissions::from_mode(0o600);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+impl std::str::FromStr for OverflowPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "drop_oldest" => Ok(Self::DropOldest),
+            "disconnect" => Ok(Self::Disconnect),
+            _ => Err(Error::Unexpected(format!("Unknown overflow policy {s}"))),
+        }
+    }
+}
+
+fn env(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.is_empty())
+}
+
+#[test]
+fn test_config_resolution_order() {
+    let file = ConfigFile {
+        sock_addr: Some("/file/xbase.socket".into()),
+        log_path: Some("/file/xbase.log".into()),
+        ..Default::default()
+    };
+    let config = Config::resolve(file, |key| match key {
+        "XDG_RUNTIME_DIR" => Some("/run/user/1000".into()),
+        LOG_PATH_ENV => Some("/env/xbase.log".into()),
+        _ => None,
+    });
+
+    assert_eq!(config.runtime_dir, PathBuf::from("/run/user/1000/xbase"));
+    assert_eq!(config.sock_addr, PathBuf::from("/file/xbase.socket"));
+    assert_eq!(config.log_path, PathBuf::from("/env/xbase.log"));
+    assert_eq!(
+        config.pid_path,
+        PathBuf::from("/run/user/1000/xbase/xbase.pid")
+    );
+    assert_eq!(
+        config.broadcast_root,
+        PathBuf::from("/run/user/1000/xbase/broadcast")
+    );
+}
@@ -4,24 +4,71 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error as ThisError;
 use typescript_type_def::TypeDef;
 
-/// Server Error due to failure while processing a `Request
+/// Server Error due to failure while processing a `Request`
+///
+/// Serialized as `{ code, msg, kind, data }`, where `kind` and `data` are the [`Error`] variant
+/// and its fields.
 #[derive(Debug, Deserialize, Serialize, TypeDef)]
 pub struct ServerError {
-    kind: String,
+    /// Stable error code, see [`ERROR_CODES`]
+    code: u16,
+    /// Human readable message
     msg: String,
+    #[serde(flatten)]
+    error: Error,
 }
 
-#[derive(ThisError, Debug)]
+/// Stable error codes by error kind, as returned by [`Error::code`], for documentation and clients.
+/// New kinds must be appended with a new code
+pub const ERROR_CODES: &[(&str, u16)] = &[
+    ("Unexpected", 1),
+    ("Setup", 2),
+    ("Lookup", 3),
+    ("Build", 4),
+    ("Run", 5),
+    ("Generate", 6),
+    ("Compile", 7),
+    ("DefinitionParsing", 8),
+    ("DefinitionLocating", 9),
+    ("DefinitionMutliFound", 10),
+    ("JoinError", 11),
+    ("SendError", 12),
+    ("MessageParse", 13),
+    ("RequestParse", 14),
+    ("Incompatible", 15),
+    ("Unauthorized", 16),
+    ("UnknownProject", 17),
+];
+
+/// Number of stderr lines kept in errors of failed processes
+pub(crate) const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(ThisError, Debug, Clone, PartialEq, Serialize, Deserialize, TypeDef)]
+#[serde(tag = "kind", content = "data")]
 pub enum Error {
-    #[error("[{0}] Failed to setup project: {1}")]
-    Setup(String, String),
-    #[error("No `{1}` found with `{0}`")]
-    /// Key, Type
-    Lookup(String, String),
-    #[error("Failed to build target/scheme: {0}")]
-    Build(String),
-    #[error("Failed to run target/scheme: {0}")]
-    Run(String),
+    #[error("[{project}] Failed to setup project: {msg}")]
+    Setup { project: String, msg: String },
+    #[error("No {item} found with `{key}`")]
+    Lookup { item: String, key: String },
+    #[error("Failed to build {target}: {msg}")]
+    Build {
+        target: String,
+        msg: String,
+        /// Exit code of the failed build process
+        exit_code: Option<i32>,
+        /// Last lines of the failed build process stderr
+        stderr: Vec<String>,
+    },
+    #[error("Failed to run target/scheme: {msg}")]
+    Run {
+        /// Target that failed to run, if known
+        target: Option<String>,
+        msg: String,
+        /// Exit code of the failed process
+        exit_code: Option<i32>,
+        /// Last lines of the failed process stderr
+        stderr: Vec<String>,
+    },
     #[error("Failed to generate project definition")]
     Generate,
     #[error("Failed to generate compile commands")]
@@ -40,69 +87,96 @@ pub enum Error {
     SendError(String),
     #[error("Failed to parse broadcast message: {0}")]
     MessageParse(String),
+    #[error("Failed to parse request: {0}")]
+    RequestParse(String),
+    #[error("Incompatible client: {0}")]
+    Incompatible(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
     #[error("{0} is not a registered project!")]
     UnknownProject(PathBuf),
 }
 
+impl Error {
+    /// Error kind, same as the variant name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Setup { .. } => "Setup",
+            Error::Lookup { .. } => "Lookup",
+            Error::Build { .. } => "Build",
+            Error::Run { .. } => "Run",
+            Error::Generate => "Generate",
+            Error::Compile => "Compile",
+            Error::DefinitionParsing(_) => "DefinitionParsing",
+            Error::DefinitionLocating => "DefinitionLocating",
+            Error::DefinitionMutliFound => "DefinitionMutliFound",
+            Error::Unexpected(_) => "Unexpected",
+            Error::JoinError(_) => "JoinError",
+            Error::SendError(_) => "SendError",
+            Error::MessageParse(_) => "MessageParse",
+            Error::RequestParse(_) => "RequestParse",
+            Error::Incompatible(_) => "Incompatible",
+            Error::Unauthorized(_) => "Unauthorized",
+            Error::UnknownProject(_) => "UnknownProject",
+        }
+    }
+
+    /// Stable error code, listed in [`ERROR_CODES`]
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::Unexpected(_) => 1,
+            Error::Setup { .. } => 2,
+            Error::Lookup { .. } => 3,
+            Error::Build { .. } => 4,
+            Error::Run { .. } => 5,
+            Error::Generate => 6,
+            Error::Compile => 7,
+            Error::DefinitionParsing(_) => 8,
+            Error::DefinitionLocating => 9,
+            Error::DefinitionMutliFound => 10,
+            Error::JoinError(_) => 11,
+            Error::SendError(_) => 12,
+            Error::MessageParse(_) => 13,
+            Error::RequestParse(_) => 14,
+            Error::Incompatible(_) => 15,
+            Error::Unauthorized(_) => 16,
+            Error::UnknownProject(_) => 17,
+        }
+    }
+
+    /// Run error without process output
+    pub fn run(target: Option<&str>, msg: impl ToString) -> Self {
+        Self::Run {
+            target: target.map(ToString::to_string),
+            msg: msg.to_string(),
+            exit_code: None,
+            stderr: vec![],
+        }
+    }
+}
+
+/// Get last lines of a process stderr
+pub fn stderr_tail(stderr: &str) -> Vec<String> {
+    let lines = stderr.trim().lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..]
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
 impl From<ServerError> for Error {
     fn from(v: ServerError) -> Self {
-        match v.kind.as_str() {
-            "Setup" => Self::Setup("".into(), v.msg),
-            "Build" => Self::Build(v.msg),
-            "Run" => Self::Run(v.msg),
-            "Generate" => Self::Generate,
-            "DefinitionParsing" => Self::DefinitionParsing(v.msg),
-            "DefinitionLocating" => Self::DefinitionLocating,
-            "DefinitionMutliFound" => Self::DefinitionMutliFound,
-            "SendError" => Self::SendError(v.msg),
-            "MessageParse" => Self::MessageParse(v.msg),
-            _ => Self::Unexpected(v.msg),
-        }
+        v.error
     }
 }
 
 impl From<&Error> for ServerError {
     fn from(err: &Error) -> Self {
-        let mut res = ServerError {
-            kind: Default::default(),
+        ServerError {
+            code: err.code(),
             msg: err.to_string(),
-        };
-        match err {
-            Error::Setup(_, _) => res.kind = "Setup".into(),
-            Error::Lookup(_, _) => res.kind = "Lookup".into(),
-            Error::Build(_) => res.kind = "Build".into(),
-            Error::Run(_) => res.kind = "Run".into(),
-            Error::Generate => res.kind = "Generate".into(),
-            Error::DefinitionParsing(_) => res.kind = "DefinitionParsing".into(),
-            Error::DefinitionLocating => res.kind = "DefinitionLocating".into(),
-            Error::DefinitionMutliFound => res.kind = "DefinitionMutliFound".into(),
-            Error::Unexpected(_) => res.kind = "Unexpected".into(),
-            Error::JoinError(_) => res.kind = "JoinError".into(),
-            Error::SendError(_) => res.kind = "SendError".into(),
-            Error::MessageParse(_) => res.kind = "MessageParse".into(),
-            Error::Compile => res.kind = "Compile".into(),
-            Error::UnknownProject(_) => res.kind = "UnknownProject".into(),
-        };
-        res
-    }
-}
-
-impl Serialize for Error {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        ServerError::from(self).serialize(serializer)
-    }
-}
-
-impl<'de> Deserialize<'de> for Error {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let error_inner = ServerError::deserialize(deserializer)?;
-        Ok(error_inner.into())
+            error: err.clone(),
+        }
     }
 }
 
@@ -161,25 +235,33 @@ pub trait IntoResult<V> {
 
 impl<V> IntoResult<V> for Option<V> {
     fn into_result<T: std::fmt::Debug>(self, typ: &str, key: T) -> Result<V, Error> {
-        self.ok_or_else(|| Error::Lookup(typ.to_string(), format!("{key:?}")))
+        self.ok_or_else(|| Error::Lookup {
+            item: typ.to_string(),
+            key: format!("{key:?}"),
+        })
     }
 }
 
 impl From<simctl::Error> for Error {
     fn from(e: simctl::Error) -> Self {
-        Self::Run(match e {
-            simctl::Error::Output { stderr, .. } => stderr
-                .trim()
-                .split(":")
-                .skip(1)
-                .collect::<String>()
-                .replace("\n", " ")
-                .trim()
-                .into(),
-            simctl::Error::Io(err) => err.to_string(),
-            simctl::Error::Json(err) => err.to_string(),
-            simctl::Error::Utf8(err) => err.to_string(),
-        })
+        match e {
+            simctl::Error::Output { stderr, .. } => Self::Run {
+                target: None,
+                msg: stderr
+                    .trim()
+                    .split(":")
+                    .skip(1)
+                    .collect::<String>()
+                    .replace("\n", " ")
+                    .trim()
+                    .into(),
+                exit_code: None,
+                stderr: stderr_tail(&stderr),
+            },
+            simctl::Error::Io(err) => Self::run(None, err),
+            simctl::Error::Json(err) => Self::run(None, err),
+            simctl::Error::Utf8(err) => Self::run(None, err),
+        }
     }
 }
 
@@ -188,3 +270,57 @@ impl<T: std::fmt::Debug> From<tokio::sync::mpsc::error::SendError<T>> for Error
         Self::SendError(format!("Channel closed, unable to send `{:?}`", v))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_error_round_trip() {
+        let errors = vec![
+            Error::Setup {
+                project: "Demo".into(),
+                msg: "Generation failure".into(),
+            },
+            Error::Lookup {
+                item: "Process".into(),
+                key: "Simulator".into(),
+            },
+            Error::Run {
+                target: Some("Demo".into()),
+                msg: "Getting target bin path failed".into(),
+                exit_code: Some(1),
+                stderr: vec!["error: no such target".into()],
+            },
+            Error::Build {
+                target: "Demo".into(),
+                msg: "build failed".into(),
+                exit_code: Some(65),
+                stderr: vec!["** BUILD FAILED **".into()],
+            },
+            Error::Compile,
+            Error::JoinError("task panicked".into()),
+            Error::UnknownProject("/tmp/Demo".into()),
+        ];
+
+        for error in errors {
+            let value = serde_json::to_value(ServerError::from(&error)).unwrap();
+            assert_eq!(value["code"], error.code());
+            assert!(ERROR_CODES.contains(&(error.kind(), error.code())));
+            assert_eq!(value["kind"], error.kind());
+            assert_eq!(value["msg"], error.to_string());
+
+            let server_error: ServerError = serde_json::from_value(value).unwrap();
+            assert_eq!(Error::from(server_error), error);
+        }
+    }
+
+    #[test]
+    fn test_error_codes_are_unique() {
+        for (i, (kind, code)) in ERROR_CODES.iter().enumerate() {
+            assert!(ERROR_CODES[i + 1..]
+                .iter()
+                .all(|(k, c)| k != kind && c != code));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! In-process daemon for integration tests.
+//!
+//! Starts the daemon server on a temporary socket and creates scripted fake projects, so that
+//! register → build → broadcast flows can be tested without xcodebuild or simctl.
+//!
+//! Daemon configuration and project runtimes are global, so a [`TestDaemon`] holds a process-wide
+//! lock while running: tests starting one wait for the previous one to stop, and can otherwise
+//! run in parallel.
+use crate::client::{Client, Listener};
+use crate::config::{self, Config, ConfigFile};
+use crate::project::{FakeProjectConfig, FAKE_PROJECT_MARKER};
+use crate::server::{self, Transport};
+use crate::{Error, Result};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::os::unix::prelude::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+
+/// How long to wait for a message before giving up
+pub const MESSAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+static NEXT_DAEMON: AtomicUsize = AtomicUsize::new(0);
+
+/// Held by the running [`TestDaemon`], as it owns the global daemon state
+static DAEMON_LOCK: Lazy<Arc<Mutex<()>>> = Lazy::new(Default::default);
+
+/// Daemon server running in the current process
+pub struct TestDaemon {
+    /// Temporary directory holding daemon runtime files and projects
+    pub dir: PathBuf,
+    /// Configuration the daemon is running with
+    pub config: Config,
+    /// Address the daemon listens on for tcp clients, if `tcp_addr` was configured
+    pub tcp_addr: Option<SocketAddr>,
+    server: JoinHandle<()>,
+    _lock: OwnedMutexGuard<()>,
+}
+
+impl TestDaemon {
+    /// Start daemon server on a temporary socket, once any other [`TestDaemon`] stopped
+    pub async fn start() -> Result<Self> {
+        Self::start_with(ConfigFile::default()).await
+    }
+
+    /// Start daemon server with the given configuration and a temporary runtime directory. A
+    /// tcp address with a `0` port listens on any free port, see [`Self::tcp_addr`].
+    pub async fn start_with(mut file: ConfigFile) -> Result<Self> {
+        let lock = DAEMON_LOCK.clone().lock_owned().await;
+        // Left behind by a daemon of a test that panicked before stopping it
+        crate::runtimes().await.clear();
+        let n = NEXT_DAEMON.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("xbase-harness-{}-{n}", std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+
+        file.runtime_dir = Some(dir.join("run"));
+        let config = Config::resolve(file, |_| None);
+        config.ensure_runtime_dirs()?;
+        config::set(config.clone());
+
+        let listener = UnixListener::bind(&config.sock_addr)?;
+        let tcp_listener = match &config.tcp_addr {
+            Some(addr) => Some(TcpListener::bind(addr).await?),
+            None => None,
+        };
+        let tcp_addr = tcp_listener.as_ref().map(|l| l.local_addr()).transpose()?;
+        let server = tokio::spawn(async move {
+            let accept_tcp = || async {
+                match &tcp_listener {
+                    Some(listener) => listener.accept().await,
+                    None => futures::future::pending().await,
+                }
+            };
+            loop {
+                tokio::select! {
+                    Ok((stream, _)) = listener.accept() => {
+                        let transport = Transport::Unix(stream.as_raw_fd());
+                        tokio::spawn(server::handle(stream, transport));
+                    }
+                    Ok((stream, addr)) = accept_tcp() => {
+                        tokio::spawn(server::handle(stream, Transport::Tcp(addr)));
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        Ok(Self {
+            dir,
+            config,
+            tcp_addr,
+            server,
+            _lock: lock,
+        })
+    }
+
+    /// Create a fake project with the given definition, returns its root
+    pub fn project(&self, config: &FakeProjectConfig) -> Result<PathBuf> {
+        let root = self.dir.join("projects").join(&config.name);
+        std::fs::create_dir_all(&root)?;
+        std::fs::write(
+            root.join(FAKE_PROJECT_MARKER),
+            serde_json::to_string_pretty(config)?,
+        )?;
+        Ok(root)
+    }
+
+    /// Connect a new client to the daemon
+    pub async fn connect(&self) -> Result<Client> {
+        Client::connect_to(&self.config.sock_addr).await
+    }
+
+    /// Connect a new client, register the given root and wait for the project state
+    pub async fn register(&self, root: &Path) -> Result<(Client, Listener)> {
+        let mut client = self.connect().await?;
+        let response = client.register(root.to_path_buf()).await?;
+        let mut listener = Listener::connect(&response).await?;
+        next_message(&mut listener, |m| {
+            m["type"] == "SetState" && m["args"]["key"] == "projectInfo"
+        })
+        .await?;
+        Ok((client, listener))
+    }
+
+    /// Stop project runtimes and the server, and remove temporary files
+    pub async fn stop(self) {
+        crate::shutdown_runtimes().await;
+        self.server.abort();
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Read messages until one matches the predicate, returns messages read including the match
+pub async fn next_message(
+    listener: &mut Listener,
+    predicate: impl Fn(&Value) -> bool,
+) -> Result<Vec<Value>> {
+    let read = async {
+        let mut messages = vec![];
+        while let Some(message) = listener.next().await {
+            let matched = predicate(&message);
+            messages.push(message);
+            if matched {
+                return Ok(messages);
+            }
+        }
+        Err(Error::Unexpected(format!(
+            "Broadcaster closed, received {messages:#?}"
+        )))
+    };
+
+    tokio::time::timeout(MESSAGE_TIMEOUT, read)
+        .await
+        .map_err(|_| Error::Unexpected("Timed out waiting for message".into()))?
+}
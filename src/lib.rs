@@ -1,6 +1,11 @@
 pub mod broadcast;
+pub mod client;
+pub mod config;
 pub mod error;
+#[cfg(feature = "harness")]
+pub mod harness;
 pub mod project;
+mod report;
 mod runner;
 mod runtime;
 pub mod server;
@@ -10,22 +15,26 @@ mod watcher;
 
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use std::time::Duration;
 use std::{path::PathBuf, sync::Arc};
-use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio::sync::{Mutex, Notify, OwnedMutexGuard};
 
 pub use {
-    broadcast::*, error::*, project::*, runner::*, runtime::*, types::*, util::*, watcher::*,
+    broadcast::*, error::*, project::*, report::*, runner::*, runtime::*, types::*, util::*,
+    watcher::*,
 };
 
-pub static SOCK_ADDR: &str = "/tmp/xbase.socket";
-pub static PID_PATH: &str = "/tmp/xbase.pid";
-pub static LOG_PATH: &str = "/tmp/xbase.log";
 pub static BIN_ROOT: &str = "$HOME/.local/share/xbase";
 
 pub type ProjectRuntimes = HashMap<PathBuf, PRMessageSender>;
 
 static RUNTIMES: Lazy<Arc<Mutex<ProjectRuntimes>>> = Lazy::new(Default::default);
 
+static SHUTDOWN: Lazy<Notify> = Lazy::new(Default::default);
+
+/// How long to wait for a runtime loop to exit on shutdown
+const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Get OwnedMutexGuard of runtimes
 #[tracing::instrument(name = "Runtimes")]
 pub async fn runtimes() -> OwnedMutexGuard<ProjectRuntimes> {
@@ -34,3 +43,66 @@ pub async fn runtimes() -> OwnedMutexGuard<ProjectRuntimes> {
     tracing::trace!("Returning");
     x
 }
+
+/// Shutdown all project runtimes and wait for their loops to exit
+#[tracing::instrument(name = "Runtimes")]
+pub async fn shutdown_runtimes() {
+    let pending = runtimes()
+        .await
+        .drain()
+        .map(|(root, runtime)| (root, runtime.shutdown()))
+        .collect::<Vec<_>>();
+
+    let pending = pending.into_iter().map(|(root, exited)| async move {
+        if tokio::time::timeout(RUNTIME_SHUTDOWN_TIMEOUT, exited)
+            .await
+            .is_err()
+        {
+            tracing::error!("Timed out waiting for {root:?} runtime to exit");
+        }
+    });
+
+    futures::future::join_all(pending).await;
+}
+
+/// Reload daemon configuration and tell project runtimes to reload their settings.
+///
+/// Log level, limits and broadcast root are applied right away, while the socket, pid
+/// and log paths only take effect after a restart.
+#[tracing::instrument(name = "Config")]
+pub async fn reload() {
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            tracing::error!("Failed to reload configuration: {err}");
+            return;
+        }
+    };
+
+    let current = config::get();
+    if config.sock_addr != current.sock_addr
+        || config.pid_path != current.pid_path
+        || config.log_path != current.log_path
+        || config.tcp_addr != current.tcp_addr
+    {
+        tracing::warn!("Socket, pid, log paths and tcp address changes require a restart");
+    }
+
+    util::tracing_setup::set_level(config.log_level);
+    config::set(config);
+    tracing::info!("Reloaded");
+
+    for runtime in runtimes().await.values() {
+        runtime.reload();
+    }
+}
+
+/// Request the daemon to shutdown
+pub fn request_shutdown() {
+    SHUTDOWN.notify_one();
+}
+
+/// Future that resolves once the daemon shutdown is requested
+pub async fn shutdown_requested() {
+    SHUTDOWN.notified().await
+}
@@ -1,50 +1,123 @@
 use fs::cleanup_daemon_runtime;
 use futures::stream::StreamExt;
+use server::Transport;
 use signal_hook::consts::signal::*;
 use signal_hook_tokio::Signals;
+use std::net::SocketAddr;
+use std::os::unix::prelude::AsRawFd;
+use std::time::{Duration, Instant};
 use tokio::fs::write;
-use tokio::net::UnixListener;
+use tokio::net::{TcpListener, TcpStream, UnixListener};
+use tokio::task::JoinSet;
 use tokio::{pin, select};
 use tracing::info;
 use tracing_setup::setup as tracing_setup;
 use xbase::*;
 
+/// How often to check whether the daemon is idle
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 #[tokio::main]
-// TODO: store futures somewhere, to gracefully close connection to clients
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        std::process::exit(client::run(args).await);
+    }
+
+    daemon().await
+}
+
+/// Run the daemon until shutdown is requested or a termination signal is received
+async fn daemon() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let os_signal_handler = tokio::spawn(handle_os_signals());
+    let mut clients = JoinSet::new();
+
+    let config = config::get();
 
     let listener = {
-        tracing_setup(LOG_PATH, tracing::Level::DEBUG, true)?;
-        cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
-        write(PID_PATH, std::process::id().to_string()).await?;
-        UnixListener::bind(SOCK_ADDR).unwrap()
+        config.ensure_runtime_dirs()?;
+        tracing_setup(&config.log_path, config.log_level, true)?;
+        cleanup_daemon_runtime(&config.pid_path, &config.sock_addr).await?;
+        write(&config.pid_path, std::process::id().to_string()).await?;
+        let listener = UnixListener::bind(&config.sock_addr).unwrap();
+        config::set_private_permissions(&config.sock_addr)?;
+        listener
+    };
+
+    let tcp_listener = match (&config.tcp_addr, &config.tcp_token) {
+        (Some(addr), Some(_)) => {
+            let listener = TcpListener::bind(addr).await?;
+            info!("Listening on tcp:{}", listener.local_addr()?);
+            Some(listener)
+        }
+        (Some(addr), None) => {
+            tracing::error!("Refusing to listen on tcp:{addr} without a token");
+            None
+        }
+        _ => None,
     };
 
+    let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+    let mut idle_since = Instant::now();
+
     pin!(os_signal_handler);
     info!("SERVER STARTED");
 
     loop {
         select! {
-            Ok((stream, _)) = listener.accept() => tokio::spawn(server::handle(stream)),
+            Ok((stream, _)) = listener.accept() => {
+                let transport = Transport::Unix(stream.as_raw_fd());
+                clients.spawn(server::handle(stream, transport));
+            },
+            Ok((stream, addr)) = accept_tcp(tcp_listener.as_ref()) => {
+                stream.set_nodelay(true).ok();
+                clients.spawn(server::handle(stream, Transport::Tcp(addr)));
+            },
+            Some(_) = clients.join_next() => {},
+            _ = idle_check.tick() => {
+                let idle_timeout = config::get().idle_timeout;
+                if idle_timeout.is_none() || !clients.is_empty() || !runtimes().await.is_empty() {
+                    idle_since = Instant::now();
+                } else if idle_timeout.map_or(false, |timeout| idle_since.elapsed() >= timeout) {
+                    info!("SERVER STOPPED: Idle for {:?}", idle_since.elapsed());
+                    break;
+                }
+            },
+            _ = shutdown_requested() => break,
             _ = &mut os_signal_handler => break,
         };
     }
 
     drop(listener);
+    drop(tcp_listener);
+
+    info!("SERVER STOPPING");
+    shutdown_runtimes().await;
+    clients.shutdown().await;
 
-    cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
+    cleanup_daemon_runtime(&config.pid_path, &config.sock_addr).await?;
 
     Ok(())
 }
 
+/// Accept tcp connections, or wait forever if tcp transport is disabled
+async fn accept_tcp(listener: Option<&TcpListener>) -> std::io::Result<(TcpStream, SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Future that await and processes for os signals.
 async fn handle_os_signals() -> Result<()> {
     let mut signals = Signals::new(&[SIGHUP, SIGTERM, SIGINT, SIGQUIT])?;
 
     while let Some(signal) = signals.next().await {
         match signal {
-            SIGHUP => {}
+            SIGHUP => {
+                tracing::info!("Reloading: Hangup Signal Received");
+                reload().await;
+            }
             SIGINT => {
                 tracing::warn!("SERVER STOPPED: Interruption Signal Received");
                 break;
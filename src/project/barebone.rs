@@ -27,6 +27,10 @@ impl ProjectData for BareboneProject {
         &self.xcodeproj.name()
     }
 
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Barebone
+    }
+
     fn targets(&self) -> &HashMap<String, TargetInfo> {
         &self.targets
     }
@@ -42,14 +46,26 @@ impl ProjectData for BareboneProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        super::test_bundles(&self.xcodeproj, self.root())
+    }
 }
 
 #[async_trait::async_trait]
-impl ProjectBuild for BareboneProject {}
+impl ProjectBuild for BareboneProject {
+    fn build_steps(&self, target: &str) -> Option<u64> {
+        let sources = super::target_sources(&self.xcodeproj, self.root(), target)?;
+        super::estimate_build_steps(&sources)
+    }
+}
 
 #[async_trait::async_trait]
 impl ProjectRun for BareboneProject {}
 
+#[async_trait::async_trait]
+impl ProjectTest for BareboneProject {}
+
 #[async_trait::async_trait]
 impl ProjectCompile for BareboneProject {
     async fn update_compile_database(&self, broadcast: &Arc<Broadcast>) -> Result<()> {
@@ -87,7 +103,7 @@ impl ProjectCompile for BareboneProject {
 
         let _all_pass = tasks_recvs
             .into_iter()
-            .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default() }))
+            .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default().success }))
             .pipe(try_join_all)
             .await
             .unwrap_or_default()
@@ -0,0 +1,227 @@
+use crate::TestCaseInfo;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Class or extension whose body is being scanned
+struct Scope {
+    name: String,
+    /// Brace depth the declaration is at
+    depth: usize,
+}
+
+/// Test methods and class declarations found in a source file
+#[derive(Default)]
+struct SourceScan {
+    /// Declared classes with their superclass, if any
+    classes: HashMap<String, Option<String>>,
+    /// `func test*()` methods of classes and extensions, whether or not they're test classes
+    methods: Vec<TestCaseInfo>,
+}
+
+/// Discover test cases in swift sources, given as files or directories to search: `func test*()`
+/// methods without parameters of classes inheriting, directly or not, from XCTestCase.
+pub(crate) fn discover_tests(sources: &[PathBuf]) -> Vec<TestCaseInfo> {
+    let files = sources.iter().flat_map(|source| {
+        if !source.is_dir() {
+            return vec![source.clone()];
+        }
+        match wax::walk("**/*.swift", source) {
+            Ok(walk) => walk.flatten().map(|entry| entry.into_path()).collect(),
+            Err(err) => {
+                tracing::error!("Failed to scan {source:?} for tests: {err}");
+                vec![]
+            }
+        }
+    });
+    let scan = files
+        .filter(|file| file.extension().map_or(false, |ext| ext == "swift"))
+        .filter_map(|file| Some((std::fs::read_to_string(&file).ok()?, file)))
+        .fold(SourceScan::default(), |mut scan, (content, file)| {
+            scan.extend(&file, &content);
+            scan
+        });
+
+    scan.test_cases()
+}
+
+impl SourceScan {
+    /// Scan source file content
+    fn extend(&mut self, file: &Path, content: &str) {
+        let mut scopes: Vec<Scope> = vec![];
+        let mut depth = 0;
+
+        for (idx, line) in content.lines().enumerate() {
+            let code = code(line);
+
+            if let Some((name, superclass)) = declaration(&code) {
+                if let Some(superclass) = superclass {
+                    self.classes.insert(name.clone(), Some(superclass));
+                } else {
+                    self.classes.entry(name.clone()).or_default();
+                }
+                scopes.push(Scope { name, depth });
+            } else if let Some(name) = test_method(&code) {
+                match scopes.last() {
+                    Some(scope) if scope.depth + 1 == depth => self.methods.push(TestCaseInfo {
+                        suite: scope.name.clone(),
+                        name,
+                        file: PathBuf::from(file),
+                        line: idx as u32 + 1,
+                    }),
+                    _ => {}
+                }
+            }
+
+            for c in code.chars() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            if code.contains('}') {
+                while scopes.last().map_or(false, |scope| depth <= scope.depth) {
+                    scopes.pop();
+                }
+            }
+        }
+    }
+
+    /// Test methods of classes inheriting from XCTestCase
+    fn test_cases(self) -> Vec<TestCaseInfo> {
+        let is_test_class = |name: &str| {
+            let mut class = name;
+            // Bounded walk up the class hierarchy, in case of cycles
+            for _ in 0..self.classes.len() + 1 {
+                match self.classes.get(class) {
+                    Some(Some(superclass)) if superclass == "XCTestCase" => return true,
+                    Some(Some(superclass)) => class = superclass,
+                    _ => return false,
+                }
+            }
+            false
+        };
+
+        let mut cases = self
+            .methods
+            .iter()
+            .filter(|case| is_test_class(&case.suite))
+            .cloned()
+            .collect::<Vec<_>>();
+        cases.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        cases
+    }
+}
+
+/// Line without comments and string literals content, good enough to count braces
+fn code(line: &str) -> String {
+    let line = line.split("//").next().unwrap_or_default();
+    let mut in_string = false;
+    line.chars()
+        .filter(|c| {
+            if *c == '"' {
+                in_string = !in_string;
+            }
+            !in_string && *c != '"'
+        })
+        .collect()
+}
+
+/// Parse `class Name: Superclass, Protocol {` or `extension Name {`, returns the name and the
+/// superclass (first inherited type) of classes
+fn declaration(code: &str) -> Option<(String, Option<String>)> {
+    let mut words = code.split_whitespace();
+    let keyword = words.find(|w| *w == "class" || *w == "extension")?;
+    let rest = words.collect::<Vec<_>>().join(" ");
+    let name_end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(rest.len());
+    let name = rest[..name_end].rsplit('.').next()?;
+    if name.is_empty() || ["func", "var", "let"].contains(&name) {
+        return None;
+    }
+
+    let superclass = match keyword {
+        "class" => rest[name_end..]
+            .split_once(':')
+            .and_then(|(_, inherited)| inherited.split([',', '{', ' ']).find(|s| !s.is_empty()))
+            .map(|s| s.rsplit('.').next().unwrap_or(s).to_string()),
+        _ => None,
+    };
+
+    Some((name.into(), superclass))
+}
+
+/// Parse `func testName()` instance methods, returns the test name
+fn test_method(code: &str) -> Option<String> {
+    let (modifiers, rest) = code.split_once("func ")?;
+    if modifiers
+        .split_whitespace()
+        .any(|m| m == "class" || m == "static")
+    {
+        return None;
+    }
+    let (name, rest) = rest.split_once('(')?;
+    let name = name.trim();
+    let is_test = name.starts_with("test") && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    (is_test && rest.trim_start().starts_with(')')).then(|| name.into())
+}
+
+#[test]
+fn test_discover_test_cases() {
+    let mut scan = SourceScan::default();
+    scan.extend(
+        Path::new("/tmp/AppTests/BaseTests.swift"),
+        r#"
+import XCTest
+
+class BaseTests: XCTestCase {
+    func helper(_ value: Int) {}
+    class func testClassMethod() {}
+}
+"#,
+    );
+    scan.extend(
+        Path::new("/tmp/AppTests/AppTests.swift"),
+        r#"
+import XCTest
+@testable import App
+
+final class AppTests: BaseTests {
+    func testAdd() throws {
+        let text = "{ not a brace"
+        XCTAssertEqual(1 + 1, 2)
+    }
+
+    // func testCommented() {}
+    func testWithArgument(_ value: Int) {}
+
+    func testAsync() async throws {
+        func testNested() {}
+    }
+}
+
+extension AppTests {
+    func testInExtension() {}
+}
+
+struct Helper {
+    func testNotInTestClass() {}
+}
+"#,
+    );
+
+    let cases = scan.test_cases();
+    let names = cases
+        .iter()
+        .map(|case| format!("{}/{}:{}", case.suite, case.name, case.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "AppTests/testAdd:6",
+            "AppTests/testAsync:14",
+            "AppTests/testInExtension:20"
+        ]
+    );
+}
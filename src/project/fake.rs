@@ -0,0 +1,201 @@
+use super::*;
+use crate::watcher::Event;
+use crate::{Error, Result};
+use process_stream::Process;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Marker file describing a fake project, see [`FakeProjectConfig`]
+pub const FAKE_PROJECT_MARKER: &str = "xbase.fake.json";
+
+/// Scripted project definition used to exercise the daemon without xcodebuild or simctl
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FakeProjectConfig {
+    /// Project name
+    pub name: String,
+    /// Project targets
+    pub targets: HashMap<String, TargetInfo>,
+    /// Shell script ran with `sh -c` in project root to build any target
+    pub build: String,
+    /// Executable, relative to project root, ran after a successful build
+    pub run: Option<PathBuf>,
+    /// Shell script ran with `sh -c` in project root to test any target, with the test filter
+    /// if any in `XBASE_TEST_FILTER`
+    pub test: Option<String>,
+    /// Test targets with the directory, relative to project root, their tests are declared in
+    pub test_targets: HashMap<String, PathBuf>,
+    /// Shell script ran with `sh -c` in project root to generate the project
+    pub generate: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct FakeProject {
+    root: PathBuf,
+    config: FakeProjectConfig,
+    num_clients: i32,
+    watchignore: Vec<String>,
+}
+
+impl FakeProject {
+    /// Create a process running the given script in project root
+    fn script(&self, script: &str) -> Process {
+        let mut process = Process::new("sh");
+        process.args(["-c", script]);
+        process.current_dir(self.root());
+        process
+    }
+}
+
+impl ProjectData for FakeProject {
+    fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Fake
+    }
+
+    fn targets(&self) -> &HashMap<String, TargetInfo> {
+        &self.config.targets
+    }
+
+    fn clients(&self) -> &i32 {
+        &self.num_clients
+    }
+
+    fn clients_mut(&mut self) -> &mut i32 {
+        &mut self.num_clients
+    }
+
+    fn watchignore(&self) -> &Vec<String> {
+        &self.watchignore
+    }
+
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        self.config
+            .test_targets
+            .iter()
+            .map(|(name, dir)| (name.clone(), vec![self.root.join(dir)]))
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectBuild for FakeProject {
+    fn build(
+        &self,
+        cfg: &BuildSettings,
+        _device: Option<&Device>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<TaskOutcome>)> {
+        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone())
+            .with_report(BuildReport::new(self.root(), self.name(), cfg));
+        let recv = task.consume(Box::new(self.script(&self.config.build)))?;
+
+        Ok((vec![], recv))
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectRun for FakeProject {
+    fn get_runner(
+        &self,
+        cfg: &BuildSettings,
+        _device: Option<&Device>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<(
+        Box<dyn Runner + Send + Sync>,
+        Vec<String>,
+        tokio::sync::mpsc::Receiver<TaskOutcome>,
+    )> {
+        let path = self
+            .config
+            .run
+            .as_ref()
+            .ok_or_else(|| Error::run(Some(&cfg.target), "no run executable"))?;
+        let (args, recv) = self.build(cfg, None, broadcast)?;
+
+        Ok((
+            Box::new(BinRunner::from_path(self.root.join(path))),
+            args,
+            recv,
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectTest for FakeProject {
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        filter: Option<&str>,
+        _device: Option<&Device>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<TaskOutcome>> {
+        let script = self
+            .config
+            .test
+            .as_ref()
+            .ok_or_else(|| Error::run(Some(&cfg.target), "no test script"))?;
+        let mut process = self.script(script);
+        process.env("XBASE_TEST_FILTER", filter.unwrap_or_default());
+        let task = Task::new(TaskKind::Test, cfg.target.as_str(), broadcast.clone());
+
+        task.consume(Box::new(process))
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectCompile for FakeProject {
+    async fn update_compile_database(&self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+        tokio::fs::write(self.root().join(".compile"), "[]").await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ProjectGenerate for FakeProject {
+    fn should_generate(&self, event: &Event) -> bool {
+        self.config.generate.is_some() && event.file_name() == FAKE_PROJECT_MARKER
+    }
+
+    async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
+        if let Some(script) = self.config.generate.as_ref() {
+            let task = Task::new(TaskKind::Generate, self.name(), broadcast.clone());
+            let process = self.script(script);
+            if !task
+                .consume(Box::new(process))?
+                .recv()
+                .await
+                .unwrap_or_default()
+                .success
+            {
+                return Err(Error::Generate);
+            }
+        }
+        self.config = read_config(self.root()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Project for FakeProject {
+    async fn new(root: &PathBuf, _broadcast: &Arc<Broadcast>) -> Result<Self> {
+        Ok(Self {
+            root: root.clone(),
+            config: read_config(root).await?,
+            num_clients: 1,
+            watchignore: generate_watchignore(root).await,
+        })
+    }
+}
+
+async fn read_config(root: &Path) -> Result<FakeProjectConfig> {
+    let content = tokio::fs::read_to_string(root.join(FAKE_PROJECT_MARKER)).await?;
+    serde_json::from_str(&content).map_err(|e| Error::DefinitionParsing(e.to_string()))
+}
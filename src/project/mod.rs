@@ -1,4 +1,7 @@
 mod barebone;
+mod discovery;
+#[cfg(feature = "harness")]
+mod fake;
 mod swift;
 mod tuist;
 mod xcodegen;
@@ -7,10 +10,17 @@ use crate::util::PathExt;
 use crate::*;
 use anyhow::Context;
 use once_cell::sync::Lazy;
+use process_stream::Process;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use xclog::{XCBuildSettings, XCLogger};
+use xcodeproj::pbxproj::{PBXBuildPhaseKind, PBXProductType, PBXTarget};
+use xcodeproj::XCodeProject;
+
+pub(crate) use discovery::discover_tests;
+#[cfg(feature = "harness")]
+pub use fake::{FakeProjectConfig, FAKE_PROJECT_MARKER};
 
 /// Project Data
 pub trait ProjectData: std::fmt::Debug {
@@ -18,6 +28,8 @@ pub trait ProjectData: std::fmt::Debug {
     fn root(&self) -> &PathBuf;
     /// Project name
     fn name(&self) -> &str;
+    /// Project kind
+    fn kind(&self) -> ProjectKind;
     /// Project targets
     fn targets(&self) -> &HashMap<String, TargetInfo>;
     /// Project clients
@@ -36,6 +48,11 @@ pub trait ProjectData: std::fmt::Debug {
     }
     /// Get Ignore patterns
     fn watchignore(&self) -> &Vec<String>;
+    /// Test targets with the source files, or directories of source files, their tests are
+    /// declared in
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        Default::default()
+    }
     /// read dir and get xcodeproj paths
     fn get_xcodeproj_paths(&self) -> Result<Vec<PathBuf>> {
         Ok(wax::walk("*.xcodeproj", &self.root())
@@ -54,12 +71,14 @@ pub trait ProjectBuild: ProjectData {
         cfg: &BuildSettings,
         device: Option<&Device>,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<TaskOutcome>)> {
         let mut args = cfg.to_args();
         let target = &cfg.target;
         let name = self.name().to_owned();
         let xcworkspace = format!("{}.xcworkspace", &name);
-        let task = Task::new(TaskKind::Build, target, broadcast.clone());
+        let task = Task::new(TaskKind::Build, target, broadcast.clone())
+            .with_steps(self.build_steps(target))
+            .with_report(BuildReport::new(self.root(), &name, cfg));
 
         args.insert(0, "build".to_string());
 
@@ -96,6 +115,11 @@ pub trait ProjectBuild: ProjectData {
         Ok((args, recv))
     }
 
+    /// Estimated number of compile and link steps building a target goes through, if known
+    fn build_steps(&self, _target: &str) -> Option<u64> {
+        None
+    }
+
     /// Get build cache root
     fn build_cache_root(&self) -> Result<String> {
         let get_build_cache_dir = fs::get_build_cache_dir(self.root())?;
@@ -104,6 +128,60 @@ pub trait ProjectBuild: ProjectData {
     }
 }
 
+/// Source file extensions compiled in their own build step
+const SOURCE_EXTENSIONS: &[&str] = &["swift", "m", "mm", "c", "cc", "cpp"];
+
+/// Estimate build steps from a target source files, a compile step per source and a link step
+pub(crate) fn estimate_build_steps(files: &[PathBuf]) -> Option<u64> {
+    let sources = files
+        .iter()
+        .filter(|file| {
+            file.extension()
+                .map_or(false, |ext| SOURCE_EXTENSIONS.iter().any(|s| ext == *s))
+        })
+        .count() as u64;
+
+    (sources > 0).then(|| sources + 1)
+}
+
+/// Unit and UI test bundle targets with the files in their sources build phase
+pub(crate) fn test_bundles(xcodeproj: &XCodeProject, root: &Path) -> HashMap<String, Vec<PathBuf>> {
+    xcodeproj
+        .targets()
+        .iter()
+        .filter(|target| {
+            matches!(
+                target.product_type,
+                PBXProductType::UnitTestBundle | PBXProductType::UiTestBundle
+            )
+        })
+        .flat_map(|target| Some((target.name?.clone(), build_phase_sources(target, root))))
+        .collect()
+}
+
+/// Files in the sources build phase of a target, None if the project has no such target
+pub(crate) fn target_sources(
+    xcodeproj: &XCodeProject,
+    root: &Path,
+    target: &str,
+) -> Option<Vec<PathBuf>> {
+    xcodeproj
+        .targets()
+        .iter()
+        .find(|info| info.name.map_or(false, |name| name == target))
+        .map(|target| build_phase_sources(target, root))
+}
+
+fn build_phase_sources(target: &PBXTarget, root: &Path) -> Vec<PathBuf> {
+    target
+        .build_phases
+        .iter()
+        .filter(|phase| matches!(phase.kind, PBXBuildPhaseKind::Sources))
+        .flat_map(|phase| phase.files.iter())
+        .flat_map(|build_file| build_file.file.as_ref()?.full_path(root).ok())
+        .collect()
+}
+
 #[async_trait::async_trait]
 pub trait ProjectRun: ProjectData + ProjectBuild {
     fn get_runner(
@@ -114,7 +192,7 @@ pub trait ProjectRun: ProjectData + ProjectBuild {
     ) -> Result<(
         Box<dyn Runner + Send + Sync>,
         Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
+        tokio::sync::mpsc::Receiver<TaskOutcome>,
     )> {
         let (args, recv) = self.build(cfg, device, broadcast)?;
 
@@ -129,6 +207,70 @@ pub trait ProjectRun: ProjectData + ProjectBuild {
     }
 }
 
+#[async_trait::async_trait]
+pub trait ProjectTest: ProjectData {
+    /// Build and run tests of a test target, optionally only those matching a filter, e.g.
+    /// `AppTests/testExample`
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        filter: Option<&str>,
+        device: Option<&Device>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<TaskOutcome>> {
+        let target = &cfg.target;
+        let name = self.name().to_owned();
+        let xcworkspace = format!("{}.xcworkspace", &name);
+        let task = Task::new(TaskKind::Test, target, broadcast.clone());
+        let scheme = cfg.scheme.as_ref().unwrap_or(target);
+
+        let mut args = vec![
+            "test".to_string(),
+            "-configuration".into(),
+            cfg.configuration.clone(),
+            "-scheme".into(),
+            scheme.clone(),
+        ];
+
+        match filter {
+            Some(filter) => args.push(format!("-only-testing:{target}/{filter}")),
+            None if cfg.scheme.is_some() => args.push(format!("-only-testing:{target}")),
+            None => {}
+        }
+
+        match device {
+            Some(device) => {
+                args.extend(device.special_build_args());
+                args.extend(["-destination".into(), format!("id={}", device.udid)]);
+            }
+            None => args.extend(["-destination".into(), "platform=macOS".into()]),
+        }
+
+        let cache_build_root = fs::get_build_cache_dir_with_config(self.root(), cfg)?;
+        args.extend_from_slice(&[
+            format!("SYMROOT={cache_build_root}"),
+            "CODE_SIGN_IDENTITY= ".into(),
+            "CODE_SIGNING_REQUIRED=NO".into(),
+            "CODE_SIGNING_ALLOWED=NO".into(),
+        ]);
+
+        if self.root().join(&xcworkspace).exists() {
+            args.extend_from_slice(&["-workspace".into(), xcworkspace]);
+        } else {
+            args.extend_from_slice(&["-project".into(), format!("{}.xcodeproj", name)]);
+        }
+
+        task.debug(format!("[{target}] xcodebuild {}", args.join(" ")));
+
+        // Raw xcodebuild output, as XCTest case results are what's parsed out of it
+        let mut process = Process::new("xcodebuild");
+        process.args(&args);
+        process.current_dir(self.root());
+
+        task.consume(Box::new(process))
+    }
+}
+
 #[async_trait::async_trait]
 pub trait ProjectCompile: ProjectData {
     /// Generate compile database in project root
@@ -163,9 +305,16 @@ pub trait ProjectGenerate: ProjectData {
 }
 
 #[async_trait::async_trait]
-/// Project Extension that can be built, ran and regenerated
+/// Project Extension that can be built, ran, tested and regenerated
 pub trait Project:
-    ProjectData + ProjectBuild + ProjectRun + ProjectCompile + ProjectGenerate + Sync + Send
+    ProjectData
+    + ProjectBuild
+    + ProjectRun
+    + ProjectTest
+    + ProjectCompile
+    + ProjectGenerate
+    + Sync
+    + Send
 {
     /// Create new project
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self>
@@ -229,13 +378,15 @@ pub trait Project:
 
         if let Some(event) = event {
             if self.should_generate(event) {
-                self.generate(broadcast).await.map_err(|err| {
-                    Error::Setup(self.name().to_string(), format!("Generation failure {err}"))
+                self.generate(broadcast).await.map_err(|err| Error::Setup {
+                    project: self.name().to_string(),
+                    msg: format!("Generation failure {err}"),
                 })?;
                 self.update_compile_database(broadcast)
                     .await
-                    .map_err(|err| {
-                        Error::Setup(self.name().to_string(), format!("Compile database: {err}"))
+                    .map_err(|err| Error::Setup {
+                        project: self.name().to_string(),
+                        msg: format!("Compile database: {err}"),
                     })?;
                 broadcast.reload_lsp_server();
                 return Ok(true);
@@ -258,6 +409,11 @@ pub type ProjectImpl = Box<dyn Project + Send + Sync>;
 /// Create a project from given client
 
 pub async fn project(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<ProjectImpl> {
+    #[cfg(feature = "harness")]
+    if root.join(FAKE_PROJECT_MARKER).exists() {
+        return Ok(Box::new(fake::FakeProject::new(root, broadcast).await?));
+    }
+
     Ok(if root.join("project.yml").exists() {
         Box::new(xcodegen::XCodeGenProject::new(root, broadcast).await?)
     } else if root.join("Package.swift").exists() {
@@ -13,6 +13,8 @@ pub struct SwiftProject {
     name: String,
     root: PathBuf,
     targets: HashMap<String, TargetInfo>,
+    /// Test targets with the directory holding their sources
+    test_targets: HashMap<String, PathBuf>,
     num_clients: i32,
     watchignore: Vec<String>,
 }
@@ -26,6 +28,10 @@ impl ProjectData for SwiftProject {
         &self.name
     }
 
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Swift
+    }
+
     fn targets(&self) -> &HashMap<String, TargetInfo> {
         &self.targets
     }
@@ -41,6 +47,13 @@ impl ProjectData for SwiftProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        self.test_targets
+            .iter()
+            .map(|(name, dir)| (name.clone(), vec![dir.clone()]))
+            .collect()
+    }
 }
 
 #[async_trait::async_trait]
@@ -50,13 +63,14 @@ impl ProjectBuild for SwiftProject {
         cfg: &BuildSettings,
         _device: Option<&Device>,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
+    ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<TaskOutcome>)> {
         let args = vec!["build", "--target", &cfg.target];
         let mut process = Process::new("/usr/bin/swift");
 
         process.args(&args);
         process.current_dir(self.root());
-        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone());
+        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone())
+            .with_report(BuildReport::new(self.root(), self.name(), cfg));
         let recv = task.consume(Box::new(process))?;
 
         Ok((vec![], recv))
@@ -73,7 +87,7 @@ impl ProjectRun for SwiftProject {
     ) -> Result<(
         Box<dyn Runner + Send + Sync>,
         Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
+        tokio::sync::mpsc::Receiver<TaskOutcome>,
     )> {
         let (args, recv) = self.build(cfg, None, broadcast)?;
 
@@ -85,9 +99,12 @@ impl ProjectRun for SwiftProject {
         if !output.status.success() {
             let stderr = String::from_utf8(output.stderr).unwrap();
             broadcast.open_logger();
-            return Err(Error::Run(format!(
-                "Getting target bin path failed {stderr}"
-            )));
+            return Err(Error::Run {
+                target: Some(cfg.target.clone()),
+                msg: "Getting target bin path failed".into(),
+                exit_code: output.status.code(),
+                stderr: crate::error::stderr_tail(&stderr),
+            });
         }
 
         // WARN: THIS MIGHT FAIL BECAUSE BUILD IS NOT YET RAN
@@ -100,6 +117,31 @@ impl ProjectRun for SwiftProject {
     }
 }
 
+#[async_trait::async_trait]
+impl ProjectTest for SwiftProject {
+    fn test(
+        &self,
+        cfg: &BuildSettings,
+        filter: Option<&str>,
+        _device: Option<&Device>,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<tokio::sync::mpsc::Receiver<TaskOutcome>> {
+        // Test targets are modules, filters are matched against `Module.Class/test`
+        let filter = match filter {
+            Some(filter) => format!("{}.{filter}", cfg.target),
+            None => format!("{}.", cfg.target),
+        };
+        let mut process = Process::new("swift");
+
+        process.args(["test", "--filter", &filter]);
+        process.current_dir(self.root());
+        let task = Task::new(TaskKind::Test, cfg.target.as_str(), broadcast.clone());
+        task.debug(format!("[{}] swift test --filter {filter}", cfg.target));
+
+        task.consume(Box::new(process))
+    }
+}
+
 #[async_trait::async_trait]
 impl ProjectCompile for SwiftProject {
     async fn update_compile_database(&self, _logger: &Arc<Broadcast>) -> Result<()> {
@@ -131,7 +173,8 @@ impl ProjectGenerate for SwiftProject {
             .consume(Box::new(process))?
             .recv()
             .await
-            .unwrap_or_default();
+            .unwrap_or_default()
+            .success;
 
         if !success {
             return Err(Error::Generate);
@@ -209,7 +252,7 @@ impl SwiftProject {
             .map(|s| s.to_string())
             .ok_or_else(|| anyhow!("expected package name field is missing!"))?;
 
-        self.targets = map
+        let targets = map
             .get("targets")
             .and_then(|v| v.as_array())
             .ok_or_else(|| anyhow!("expected package target field is missing!"))?
@@ -217,23 +260,36 @@ impl SwiftProject {
             .flat_map(|v| v.as_object())
             .flat_map(|target_info| {
                 let name = target_info.get("name")?.as_str()?.to_string();
-                if !target_info
+                let is_test = target_info
                     .get("type")
                     .and_then(|s| s.as_str())
                     .map(|s| s == "test")
-                    .unwrap_or_default()
-                {
-                    Some((
-                        name,
-                        TargetInfo {
-                            platform: PBXTargetPlatform::MacOS.to_string(),
-                            // TODO: get swift configurations
-                            configurations: vec!["Debug".into()],
-                        },
-                    ))
-                } else {
-                    None
-                }
+                    .unwrap_or_default();
+                let path = target_info.get("path").and_then(|p| p.as_str());
+                Some((name, is_test, path.map(PathBuf::from)))
+            })
+            .collect::<Vec<_>>();
+
+        self.targets = targets
+            .iter()
+            .filter(|(_, is_test, _)| !is_test)
+            .map(|(name, ..)| {
+                let info = TargetInfo {
+                    platform: PBXTargetPlatform::MacOS.to_string(),
+                    // TODO: get swift configurations
+                    configurations: vec!["Debug".into()],
+                };
+                (name.clone(), info)
+            })
+            .collect();
+
+        // Test targets sources are in Tests/<name> unless a path is given
+        self.test_targets = targets
+            .into_iter()
+            .filter(|(_, is_test, _)| *is_test)
+            .map(|(name, _, path)| {
+                let path = path.unwrap_or_else(|| PathBuf::from("Tests").join(&name));
+                (name, self.root.join(path))
             })
             .collect();
 
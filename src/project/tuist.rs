@@ -38,6 +38,10 @@ impl ProjectData for TuistProject {
         &self.xcodeproj.name()
     }
 
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::Tuist
+    }
+
     fn targets(&self) -> &HashMap<String, TargetInfo> {
         &self.targets
     }
@@ -53,6 +57,10 @@ impl ProjectData for TuistProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        super::test_bundles(&self.xcodeproj, self.root())
+    }
 }
 #[async_trait::async_trait]
 impl ProjectCompile for TuistProject {
@@ -111,7 +119,7 @@ impl ProjectCompile for TuistProject {
 
         let _all_pass = tasks_recvs
             .into_iter()
-            .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default() }))
+            .map(|mut t| tokio::spawn(async move { t.recv().await.unwrap_or_default().success }))
             .pipe(try_join_all)
             .await
             .unwrap_or_default()
@@ -299,7 +307,15 @@ impl Project for TuistProject {
 }
 
 #[async_trait::async_trait]
-impl ProjectBuild for TuistProject {}
+impl ProjectBuild for TuistProject {
+    fn build_steps(&self, target: &str) -> Option<u64> {
+        let sources = super::target_sources(&self.xcodeproj, self.root(), target)?;
+        super::estimate_build_steps(&sources)
+    }
+}
 
 #[async_trait::async_trait]
 impl ProjectRun for TuistProject {}
+
+#[async_trait::async_trait]
+impl ProjectTest for TuistProject {}
@@ -28,6 +28,10 @@ impl ProjectData for XCodeGenProject {
         &self.xcodeproj.name()
     }
 
+    fn kind(&self) -> ProjectKind {
+        ProjectKind::XCodeGen
+    }
+
     fn targets(&self) -> &HashMap<String, TargetInfo> {
         &self.targets
     }
@@ -43,6 +47,10 @@ impl ProjectData for XCodeGenProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn test_targets(&self) -> HashMap<String, Vec<PathBuf>> {
+        super::test_bundles(&self.xcodeproj, self.root())
+    }
 }
 
 #[async_trait::async_trait]
@@ -66,7 +74,8 @@ impl ProjectCompile for XCodeGenProject {
             .consume(Box::new(xclogger))?
             .recv()
             .await
-            .unwrap_or_default();
+            .unwrap_or_default()
+            .success;
         if success {
             let compile_db = CC::new(compile_commands.lock().await.to_vec());
             if compile_db.is_empty() {
@@ -179,10 +188,10 @@ impl Project for XCodeGenProject {
         } else {
             tracing::info!("Generating xcodeproj ...");
             if let Err(err) = project.generate(broadcast).await {
-                return Err(Error::Setup(
-                    project.name().to_string(),
-                    format!("Generation failure {err}"),
-                ));
+                return Err(Error::Setup {
+                    project: project.name().to_string(),
+                    msg: format!("Generation failure {err}"),
+                });
             };
         }
 
@@ -192,7 +201,15 @@ impl Project for XCodeGenProject {
 }
 
 #[async_trait::async_trait]
-impl ProjectBuild for XCodeGenProject {}
+impl ProjectBuild for XCodeGenProject {
+    fn build_steps(&self, target: &str) -> Option<u64> {
+        let sources = super::target_sources(&self.xcodeproj, self.root(), target)?;
+        super::estimate_build_steps(&sources)
+    }
+}
 
 #[async_trait::async_trait]
 impl ProjectRun for XCodeGenProject {}
+
+#[async_trait::async_trait]
+impl ProjectTest for XCodeGenProject {}
@@ -0,0 +1,259 @@
+//! SARIF and JUnit reports of build outcomes, for review bots and CI to consume
+use crate::{BuildSettings, Diagnostic, DiagnosticSeverity, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// SARIF schema reports are written with
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Base id of uris relative to project root in SARIF reports
+const SARIF_ROOT_ID: &str = "SRCROOT";
+
+/// Reports to write once a build finishes
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    root: PathBuf,
+    project: String,
+    target: String,
+    sarif: Option<PathBuf>,
+    junit: Option<PathBuf>,
+    started: Instant,
+}
+
+impl BuildReport {
+    /// Create build report with paths from build settings, falling back to configured report
+    /// directory. None if no report should be written.
+    pub fn new(root: &Path, project: &str, settings: &BuildSettings) -> Option<Self> {
+        let report_dir = crate::config::get().report_dir.clone();
+        let path = |path: &Option<PathBuf>, extension: &str| {
+            path.as_ref().map(|path| root.join(path)).or_else(|| {
+                let name = format!("{}.{extension}", settings.target);
+                report_dir.as_ref().map(|dir| dir.join(project).join(name))
+            })
+        };
+        let sarif = path(&settings.sarif, "sarif");
+        let junit = path(&settings.junit, "junit.xml");
+        if sarif.is_none() && junit.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            root: root.to_path_buf(),
+            project: project.into(),
+            target: settings.target.clone(),
+            sarif,
+            junit,
+            started: Instant::now(),
+        })
+    }
+
+    /// Write reports of the finished build
+    pub fn write(&self, diagnostics: &[Diagnostic], success: bool) -> Result<()> {
+        let duration = self.started.elapsed();
+        if let Some(path) = &self.sarif {
+            let sarif = self.sarif(diagnostics, success);
+            write(path, serde_json::to_string_pretty(&sarif)?)?;
+        }
+        if let Some(path) = &self.junit {
+            write(path, self.junit(diagnostics, success, duration))?;
+        }
+        Ok(())
+    }
+
+    /// SARIF log with one result per diagnostic
+    fn sarif(&self, diagnostics: &[Diagnostic], success: bool) -> Value {
+        let location = |file: &Path, line: u32, column: u32| {
+            let mut region = json!({ "startLine": line });
+            if column > 0 {
+                region["startColumn"] = column.into();
+            }
+            let artifact = match file.strip_prefix(&self.root) {
+                Ok(path) => json!({ "uri": uri_path(path), "uriBaseId": SARIF_ROOT_ID }),
+                Err(_) => json!({ "uri": format!("file://{}", uri_path(file)) }),
+            };
+            json!({ "physicalLocation": { "artifactLocation": artifact, "region": region } })
+        };
+
+        let results = diagnostics
+            .iter()
+            .map(|d| {
+                let related = d
+                    .notes
+                    .iter()
+                    .map(|note| {
+                        let mut location = location(&note.file, note.line, note.column);
+                        location["message"] = json!({ "text": note.message });
+                        location
+                    })
+                    .collect::<Vec<_>>();
+                json!({
+                    "level": match d.severity {
+                        DiagnosticSeverity::Error => "error",
+                        DiagnosticSeverity::Warning => "warning",
+                        DiagnosticSeverity::Note => "note",
+                    },
+                    "message": { "text": d.message },
+                    "locations": [location(&d.file, d.line, d.column)],
+                    "relatedLocations": related,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "xbase",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/xbase-lab/xbase",
+                    }
+                },
+                "originalUriBaseIds": {
+                    SARIF_ROOT_ID: { "uri": format!("file://{}/", uri_path(&self.root)) }
+                },
+                "invocations": [{ "executionSuccessful": success }],
+                "results": results,
+            }]
+        })
+    }
+
+    /// JUnit summary with the build as a single test case, failing with the build errors
+    fn junit(&self, diagnostics: &[Diagnostic], success: bool, duration: Duration) -> String {
+        let time = format!("{:.3}", duration.as_secs_f64());
+        let (project, target) = (escape(&self.project), escape(&self.target));
+        let failures = if success { 0 } else { 1 };
+        let format = |severity: DiagnosticSeverity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .map(|d| {
+                    let location = format!("{}:{}:{}", d.file.display(), d.line, d.column);
+                    escape(&format!("{location}: {}\n", d.message))
+                })
+                .collect::<String>()
+        };
+
+        let mut case =
+            format!(r#"    <testcase name="{target}" classname="{project}" time="{time}">"#);
+        case.push('\n');
+        if !success {
+            let errors = format(DiagnosticSeverity::Error);
+            let count = errors.lines().count();
+            case += &format!(
+                "      <failure type=\"BuildFailure\" message=\"Build failed with {count} error(s)\">{errors}</failure>\n"
+            );
+        }
+        let warnings = format(DiagnosticSeverity::Warning);
+        if !warnings.is_empty() {
+            case += &format!("      <system-out>{warnings}</system-out>\n");
+        }
+        case += "    </testcase>\n";
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="xbase" tests="1" failures="{failures}" errors="0" time="{time}">
+  <testsuite name="{project}" tests="1" failures="{failures}" errors="0" skipped="0" time="{time}">
+{case}  </testsuite>
+</testsuites>
+"#
+        )
+    }
+}
+
+/// Write report content, creating parent directories
+fn write(path: &Path, content: String) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Escape xml text and attribute values
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Percent-encode a path to be used in a uri, keeping `/` separators
+fn uri_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn test_report() -> (BuildReport, Vec<Diagnostic>) {
+    let settings = BuildSettings {
+        target: "App".into(),
+        configuration: "Debug".into(),
+        scheme: None,
+        sarif: Some("build.sarif".into()),
+        junit: None,
+    };
+    let report = BuildReport::new(Path::new("/tmp/App"), "App", &settings).unwrap();
+    let mut error =
+        Diagnostic::parse("/tmp/App/Sources/A.swift:4:10: error: invalid redeclaration of 'foo()'")
+            .unwrap();
+    error.notes.push(crate::DiagnosticNote {
+        file: "/tmp/App/Sources/A.swift".into(),
+        line: 2,
+        column: 10,
+        message: "'foo()' previously declared here".into(),
+    });
+    let warning = Diagnostic::parse("/usr/include/b.h:1:1: warning: <deprecated> & unused");
+    let diagnostics = vec![error, warning.unwrap()];
+    (report, diagnostics)
+}
+
+#[test]
+fn test_sarif_report() {
+    let (report, diagnostics) = test_report();
+    assert_eq!(report.sarif, Some(PathBuf::from("/tmp/App/build.sarif")));
+
+    let sarif = report.sarif(&diagnostics, false);
+    let run = &sarif["runs"][0];
+    let results = run["results"].as_array().unwrap();
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(run["invocations"][0]["executionSuccessful"], false);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(location["artifactLocation"]["uri"], "Sources/A.swift");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], SARIF_ROOT_ID);
+    assert_eq!(location["region"]["startLine"], 4);
+    assert_eq!(results[0]["relatedLocations"][0]["region"]["startLine"], 2);
+    assert_eq!(results[1]["level"], "warning");
+    assert_eq!(
+        results[1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "file:///usr/include/b.h"
+    );
+    assert_eq!(uri_path(Path::new("/a b/ü.swift")), "/a%20b/%C3%BC.swift");
+}
+
+#[test]
+fn test_junit_report() {
+    let (report, diagnostics) = test_report();
+    let junit = report.junit(&diagnostics, false, Duration::from_millis(1500));
+    assert!(junit.contains(r#"<testsuite name="App" tests="1" failures="1""#));
+    assert!(junit.contains(r#"<testcase name="App" classname="App" time="1.500">"#));
+    assert!(junit.contains("Build failed with 1 error(s)"));
+    assert!(junit.contains("invalid redeclaration of &apos;foo()&apos;"));
+    assert!(junit.contains("&lt;deprecated&gt; &amp; unused"));
+
+    let junit = report.junit(&[], true, Duration::from_secs(2));
+    assert!(junit.contains(r#"failures="0""#));
+    assert!(!junit.contains("<failure"));
+}
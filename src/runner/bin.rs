@@ -23,7 +23,7 @@ impl BinRunner {
 impl Runner for BinRunner {
     async fn run<'a>(&self, _task: &Task) -> Result<Process> {
         if !self.path.exists() {
-            return Err(Error::Run(format!("{:?} doesn't exist!", self.path)));
+            return Err(Error::run(None, format!("{:?} doesn't exist!", self.path)));
         }
 
         Ok(Process::new(&self.path))
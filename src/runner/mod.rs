@@ -55,17 +55,20 @@ impl Watchable for RunService {
 
         let mut handler = self.handler.clone().lock_owned().await;
 
-        handler.take().map(|v| {
-            v.process().abort();
-            v.inner().abort();
-        });
+        handler.take().map(|v| v.stop());
 
         let device = self.device.as_ref();
         let target = &settings.target;
         let (runner, _args, mut recv) = project.get_runner(&settings, device, broadcast)?;
 
-        if !recv.recv().await.unwrap_or_default() {
-            return Err(crate::Error::Run(format!("{target} build failed")));
+        let outcome = recv.recv().await.unwrap_or_default();
+        if !outcome.success {
+            return Err(crate::Error::Build {
+                target: target.clone(),
+                msg: "build failed".into(),
+                exit_code: outcome.exit_code,
+                stderr: outcome.stderr,
+            });
         }
 
         let task = Task::new(TaskKind::Run, target, broadcast.clone());
@@ -73,7 +76,7 @@ impl Watchable for RunService {
         let runner = runner.run(&task).await?;
         let broadcast = Arc::downgrade(broadcast);
 
-        *handler = Some(RunHandler::new(target, runner, broadcast)?);
+        *handler = Some(RunHandler::new(target, runner, broadcast, task.id())?);
 
         Ok(())
     }
@@ -90,10 +93,12 @@ impl Watchable for RunService {
 
     /// Drop watchable for watching a given file system
     async fn discard(&self) {
-        self.handler.clone().lock_owned().await.take().map(|v| {
-            v.process().abort();
-            v.inner().abort();
-        });
+        self.handler
+            .clone()
+            .lock_owned()
+            .await
+            .take()
+            .map(|v| v.stop());
     }
 }
 
@@ -101,12 +106,21 @@ impl Watchable for RunService {
 pub struct RunHandler {
     process: Process,
     inner: JoinHandle<Result<()>>,
+    /// Run task id, finished when the runner is stopped
+    task: u32,
+    broadcast: Weak<Broadcast>,
 }
 
 impl RunHandler {
     // Change the status of the process to running
-    pub fn new(target: &String, mut process: Process, broadcast: Weak<Broadcast>) -> Result<Self> {
+    pub fn new(
+        target: &String,
+        mut process: Process,
+        broadcast: Weak<Broadcast>,
+        task: u32,
+    ) -> Result<Self> {
         let target = target.clone();
+        let weak_broadcast = broadcast.clone();
         let mut stream = process.spawn_and_stream()?;
         let abort = process.aborter().unwrap();
 
@@ -142,7 +156,7 @@ impl RunHandler {
                         } else {
                             broadcast.log_error("Device Disconnected");
                         }
-                        broadcast.finish_current_task(success);
+                        broadcast.finish_task(task, success);
                         tracing::info!("[{target}] Runner Closed");
                         break;
                     }
@@ -154,7 +168,21 @@ impl RunHandler {
             Ok(())
         });
 
-        Ok(Self { process, inner })
+        Ok(Self {
+            process,
+            inner,
+            task,
+            broadcast: weak_broadcast,
+        })
+    }
+
+    /// Abort the runner process and finish its task as canceled
+    pub fn stop(&self) {
+        self.process.abort();
+        self.inner.abort();
+        if let Some(broadcast) = self.broadcast.upgrade() {
+            broadcast.cancel_task(self.task);
+        }
     }
 
     /// Get a reference to the run service handler's process.
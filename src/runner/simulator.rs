@@ -34,7 +34,7 @@ impl SimulatorRunner {
 
     pub async fn boot<'a>(&self, task: &Task) -> Result<()> {
         match pid::get_pid_by_name("Simulator") {
-            Err(Error::Lookup(_, _)) => {
+            Err(Error::Lookup { .. }) => {
                 task.info(format!("[Simulator] Launching"));
                 Command::new("open")
                     .args(&["-a", "Simulator"])
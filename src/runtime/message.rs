@@ -1,7 +1,11 @@
-use crate::server::{BuildRequest, RunRequest};
-use crate::{Event, PathExt};
-use std::{collections::HashSet, path::PathBuf};
-use tokio::sync::mpsc;
+use super::RuntimeInfo;
+use crate::server::{BuildRequest, RunRequest, TestRequest};
+use crate::{
+    ActiveTasks, DiagnosticsStore, Event, ListenerStream, ListenerTokens, PathExt, Subscription,
+    TaskInfo,
+};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use tokio::sync::{mpsc, oneshot, Notify};
 
 /// Project Runime Message
 #[derive(Debug)]
@@ -12,10 +16,22 @@ pub enum PRMessage {
     Connect(u32),
     /// Disconnect from client
     Disconnect(u32),
+    /// Resend state to a client that resumed its session
+    Resume(u32),
+    /// Reload project settings and restart watcher
+    Reload,
     /// Process Run Request
     Run(RunRequest),
     /// Process Build Request
     Build(BuildRequest),
+    /// Process Test Request
+    Test(TestRequest),
+    /// Report runtime information
+    Status(oneshot::Sender<RuntimeInfo>),
+    /// Stop running tasks, disconnect clients and exit runtime loop
+    Shutdown(oneshot::Sender<()>),
+    /// Attach a stream to receive broadcast messages for a client
+    Attach(u32, ListenerStream, Subscription),
 }
 
 #[derive(Debug)]
@@ -28,6 +44,14 @@ pub struct PRMessageSender {
     sender: mpsc::UnboundedSender<PRMessage>,
     /// Connect Cilents
     clients: HashSet<u32>,
+    /// Tokens clients must connect to the broadcaster with
+    tokens: ListenerTokens,
+    /// Tasks under progress, readable while the runtime is busy
+    tasks: ActiveTasks,
+    /// Diagnostics of the last build of each target
+    diagnostics: DiagnosticsStore,
+    /// Notifier to shutdown the runtime ahead of queued messages
+    shutdown: Arc<Notify>,
 }
 
 impl PRMessageSender {
@@ -35,12 +59,20 @@ impl PRMessageSender {
         root: &PathBuf,
         baddress: &PathBuf,
         sender: &mpsc::UnboundedSender<PRMessage>,
+        tokens: &ListenerTokens,
+        tasks: &ActiveTasks,
+        diagnostics: &DiagnosticsStore,
+        shutdown: &Arc<Notify>,
     ) -> Self {
         Self {
             root: root.clone(),
             broadcaster_adderss: baddress.clone(),
             sender: sender.clone(),
             clients: HashSet::default(),
+            tokens: tokens.clone(),
+            tasks: tasks.clone(),
+            diagnostics: diagnostics.clone(),
+            shutdown: shutdown.clone(),
         }
     }
 
@@ -80,6 +112,56 @@ impl PRMessageSender {
         &self.broadcaster_adderss
     }
 
+    /// Issue a new token for a client to connect to the broadcaster with
+    pub fn issue_token(&self, id: u32) -> String {
+        self.tokens.issue(id)
+    }
+
+    /// Check the broadcaster token of a client
+    pub fn verify_token(&self, id: u32, token: &str) -> bool {
+        self.tokens.verify(id, token)
+    }
+
+    /// Get connected clients ids
+    pub fn clients(&self) -> Vec<u32> {
+        let mut clients = self.clients.iter().copied().collect::<Vec<_>>();
+        clients.sort_unstable();
+        clients
+    }
+
+    /// Get tasks under progress, oldest first
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.tasks.list()
+    }
+
+    /// Get diagnostics of the last build of each target
+    pub fn diagnostics(&self) -> &DiagnosticsStore {
+        &self.diagnostics
+    }
+
+    /// Query runtime information, the receiver errors if the runtime is no longer running
+    pub fn status(&self) -> oneshot::Receiver<RuntimeInfo> {
+        let (tx, rx) = oneshot::channel();
+        self.send(PRMessage::Status(tx));
+        rx
+    }
+
+    /// Reload project settings and watchignore
+    pub fn reload(&self) {
+        self.send(PRMessage::Reload);
+    }
+
+    /// Shutdown runtime, the receiver resolves once the runtime loop has exited.
+    ///
+    /// The runtime is notified aside of its queue, so that it shuts down without waiting for
+    /// queued messages or the one under processing.
+    pub fn shutdown(&self) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        self.send(PRMessage::Shutdown(tx));
+        self.shutdown.notify_one();
+        rx
+    }
+
     pub fn contains(&self, value: &u32) -> bool {
         self.clients.contains(value)
     }
@@ -1,10 +1,28 @@
 mod message;
+mod state;
 pub use message::*;
 
+use state::WatchState;
+
 use crate::{server::*, *};
-use std::{path::PathBuf, sync::Arc};
-use tokio::sync::{mpsc, Notify};
+use serde::Serialize;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tokio::sync::{mpsc, oneshot, Notify};
 use tracing::{info, instrument};
+use typescript_type_def::TypeDef;
+
+/// Information a running ProjectRuntime reports about itself
+#[derive(Debug, Serialize, TypeDef)]
+pub struct RuntimeInfo {
+    /// Project kind
+    pub kind: ProjectKind,
+    /// Build/Run requests subscribed to changes
+    pub watchlist: Vec<String>,
+    /// Tasks currently under progress, oldest first
+    pub tasks: Vec<TaskInfo>,
+    /// Message delivery statistics of connected clients
+    pub listeners: Vec<ListenerStats>,
+}
 
 /// ProjectRuntime
 pub struct ProjectRuntime {
@@ -22,10 +40,18 @@ pub struct ProjectRuntime {
     watcher_subscribers: WatchSubscribers,
     /// Build/Run requests subscribed to changes
     watcher_state: WatcherState,
+    /// Run requests ran once, kept to be stopped on shutdown
+    runners: HashMap<String, RunService>,
+    /// Watch subscriptions persisted in daemon state
+    watchers: Vec<WatchState>,
+    /// Test cases discovered in test targets sources, indexed by target
+    tests: HashMap<String, Vec<TestCaseInfo>>,
     /// Connect clients id
     clients: u32,
     /// Notifer to notify listeners that this runtime is no longer active
     abort: Arc<Notify>,
+    /// Notifier to shutdown the runtime ahead of queued messages
+    shutdown: Arc<Notify>,
 }
 
 impl ProjectRuntime {
@@ -33,17 +59,30 @@ impl ProjectRuntime {
     pub async fn new(root: PathBuf) -> Result<(Self, PRMessageSender)> {
         info!("[Initializing] ------------------------");
         let (sender, receiver) = mpsc::unbounded_channel::<PRMessage>();
+        let shutdown: Arc<Notify> = Default::default();
         let broadcaster = Arc::new(Broadcast::new(&root).await?);
         let project = project::project(&root, &broadcaster).await?;
-        let rsender = PRMessageSender::new(&root, broadcaster.address(), &sender);
+        let rsender = PRMessageSender::new(
+            &root,
+            broadcaster.address(),
+            &sender,
+            broadcaster.tokens(),
+            broadcaster.tasks(),
+            broadcaster.diagnostics(),
+            &shutdown,
+        );
         let name = project.name().to_string();
         let watcher_subscribers = WatchSubscribers::new(&name);
         let runtime = Self {
             name,
             clients: Default::default(),
             abort: Default::default(),
+            shutdown,
             watcher_state: WatcherState::new(),
             watcher_subscribers,
+            runners: Default::default(),
+            watchers: Default::default(),
+            tests: Default::default(),
             broadcaster,
             project,
             receiver,
@@ -63,6 +102,90 @@ impl ProjectRuntime {
             self.broadcaster.error(format!("[{}]  {err}", self.name));
         };
 
+        self.discover_tests(None).await;
+        self.start_watcher();
+        self.on_connect(id);
+        self.restore_watchers().await;
+
+        info!("[Initialized] -------------------------");
+        // Shutdown is notified aside of the queue, so that it doesn't wait for messages queued
+        // before it nor for the one under processing (e.g. a build a runner waits for)
+        let shutdown = self.shutdown.clone();
+        let requested = loop {
+            tokio::select! {
+                biased;
+                _ = shutdown.notified() => break true,
+                running = self.next_message() => if !running { break false },
+            }
+        };
+        if requested {
+            self.on_shutdown().await;
+        }
+        info!("[Dropped]");
+        // Answer shutdown requests, dropping messages queued before them
+        while let Ok(message) = self.receiver.try_recv() {
+            if let PRMessage::Shutdown(tx) = message {
+                tx.send(()).ok();
+            }
+        }
+    }
+
+    /// Wait for the next message and process it, returns false once the runtime should stop
+    async fn next_message(&mut self) -> bool {
+        let message = match self.receiver.recv().await {
+            Some(message) => message,
+            None => return false,
+        };
+        match message {
+            PRMessage::Connect(id) => self.on_connect(id),
+            PRMessage::Disconnect(id) => {
+                info!("Disconnected [{id}]");
+                self.clients -= 1;
+                self.broadcaster.tokens().revoke(id);
+                self.broadcaster.send(Some(id), Message::Disconnect);
+                if self.clients.eq(&0) {
+                    let root = self.project.root().clone();
+                    state::remove(&root);
+                    self.broadcaster.abort();
+                    self.abort.notify_waiters();
+                    tokio::spawn(async move { runtimes().await.remove(&root) });
+                    return false;
+                }
+            }
+            PRMessage::FSEvent(event) => self.on_fs_event(event).await,
+            PRMessage::Run(req) => self.on_run(req).await,
+            PRMessage::Build(req) => self.on_build(req).await,
+            PRMessage::Test(req) => self.on_test(req),
+            PRMessage::Status(tx) => self.on_status(tx),
+            PRMessage::Resume(id) => self.on_resume(id),
+            PRMessage::Reload => self.on_reload().await,
+            PRMessage::Attach(id, stream, subscription) => {
+                self.broadcaster.attach(id, stream, subscription).await;
+                self.on_resume(id);
+            }
+            PRMessage::Shutdown(tx) => {
+                self.on_shutdown().await;
+                tx.send(()).ok();
+                return false;
+            }
+        };
+        true
+    }
+
+    fn on_connect(&mut self, id: u32) {
+        info!("Connected [{id}]");
+        self.clients += 1;
+        let msg = format!("[{}] Registered", self.name);
+        self.broadcaster.info_with_id(id, msg);
+        self.set_client_project_state(Some(id));
+        self.set_client_runner_state(id);
+    }
+
+    /// Start watcher with current project watchignore, stopping the previous one if any
+    fn start_watcher(&mut self) {
+        self.abort.notify_waiters();
+        self.abort = Default::default();
+
         tokio::spawn(
             Watcher::new(
                 &self.name,
@@ -74,36 +197,29 @@ impl ProjectRuntime {
             )
             .start(),
         );
-        self.on_connect(id);
+    }
 
-        info!("[Initialized] -------------------------");
-        while let Some(message) = self.receiver.recv().await {
-            match message {
-                PRMessage::Connect(id) => self.on_connect(id),
-                PRMessage::Disconnect(id) => {
-                    info!("Disconnected [{id}]");
-                    self.clients -= 1;
-                    self.broadcaster.send(Some(id), Message::Disconnect);
-                    if self.clients.eq(&0) {
-                        self.broadcaster.abort();
-                        self.abort.notify_waiters();
-                        tokio::spawn(async move { runtimes().await.remove(self.project.root()) });
-                        break;
-                    }
-                }
-                PRMessage::FSEvent(event) => self.on_fs_event(event).await,
-                PRMessage::Run(req) => self.on_run(req).await,
-                PRMessage::Build(req) => self.on_build(req).await,
+    /// Reread project settings and watchignore, keeping clients and watch subscribers
+    async fn on_reload(&mut self) {
+        info!("Reloading");
+        let root = self.project.root().clone();
+        match project::project(&root, &self.broadcaster).await {
+            Ok(project) => self.project = project,
+            Err(err) => {
+                let msg = format!("[{}] Failed to reload project: {err}", self.name);
+                self.broadcaster.error(msg);
+                return;
             }
-        }
-        info!("[Dropped]");
+        };
+        self.discover_tests(None).await;
+        self.start_watcher();
+        self.set_client_project_state(None);
+        info!("Reloaded");
     }
 
-    fn on_connect(&mut self, id: u32) {
-        info!("Connected [{id}]");
-        self.clients += 1;
-        let msg = format!("[{}] Registered", self.name);
-        self.broadcaster.info_with_id(id, msg);
+    /// Send current state to a client that is already connected
+    fn on_resume(&mut self, id: u32) {
+        info!("Resumed [{id}]");
         self.set_client_project_state(Some(id));
         self.set_client_runner_state(id);
     }
@@ -119,11 +235,18 @@ impl ProjectRuntime {
             || event.is_rename_event() && !event.is_seen()
         {
             let ensure_setup = self.project.ensure_setup(Some(&event), &self.broadcaster);
-            match ensure_setup.await {
-                Err(e) => self.broadcaster.error(format!("[{name}] {e}")),
-                Ok(true) => self.set_client_project_state(None),
-                _ => {}
+            let updated = match ensure_setup.await {
+                Err(e) => {
+                    self.broadcaster.error(format!("[{name}] {e}"));
+                    false
+                }
+                Ok(updated) => updated,
             };
+            // Test targets may have changed when the project was regenerated, rescan all of them
+            let changed = (!updated).then(|| &event);
+            if self.discover_tests(changed).await || updated {
+                self.set_client_project_state(None);
+            }
         }
 
         self.watcher_subscribers
@@ -142,8 +265,13 @@ impl ProjectRuntime {
             req.operation.is_watch()
         } else {
             self.watcher_subscribers.remove(&req).await;
+            self.persist_watcher(req.to_string(), None);
             return;
         };
+        if let Some(previous) = self.runners.remove(&req.to_string()) {
+            previous.discard().await;
+        }
+        let watcher = is_watch.then(|| WatchState::Run(req.clone()));
         let service = req.into_service();
         let event = Event::default();
         let res = service.trigger(&mut self.project, &event, &self.broadcaster);
@@ -152,8 +280,11 @@ impl ProjectRuntime {
             self.broadcaster.error(msg);
         }
         info!("Ran {}", service.settings.target);
-        if is_watch {
+        if let Some(watcher) = watcher {
+            self.persist_watcher(service.to_string(), Some(watcher));
             self.watcher_subscribers.add(service);
+        } else {
+            self.runners.insert(service.to_string(), service);
         }
     }
 
@@ -163,26 +294,122 @@ impl ProjectRuntime {
             req.operation.is_watch()
         } else {
             self.watcher_subscribers.remove(&req).await;
+            self.persist_watcher(req.to_string(), None);
             return;
         };
 
         info!("Building {}", req.settings.target);
+        let watcher = is_watch.then(|| WatchState::Build(req.clone()));
+        let service = req.into_service(&self.broadcaster);
         let event = Event::default();
-        let res = req.trigger(&mut self.project, &event, &self.broadcaster);
+        let res = service.trigger(&mut self.project, &event, &self.broadcaster);
         if let Err(err) = res.await {
             let msg = format!("[{}] failed to start runner {err}", self.name);
             self.broadcaster.error(msg);
         }
-        info!("Built {}", req.settings.target);
-        if is_watch {
-            self.watcher_subscribers.add(req);
+        info!("Built {}", service.request.settings.target);
+        if let Some(watcher) = watcher {
+            self.persist_watcher(service.to_string(), Some(watcher));
+            self.watcher_subscribers.add(service);
         }
     }
 
+    #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
+    fn on_test(&mut self, req: TestRequest) {
+        info!("Testing {}", req.settings.target);
+        let device = Devices::from_lookup(req.device.clone());
+        let filter = req.filter.as_deref();
+        let res = self
+            .project
+            .test(&req.settings, filter, device.as_ref(), &self.broadcaster);
+        if let Err(err) = res {
+            let msg = format!("[{}] failed to start tests {err}", self.name);
+            self.broadcaster.error(msg);
+        }
+    }
+
+    /// Discover test cases of test targets the event file is a source of, or of all test targets
+    /// without event. Sources are scanned on a blocking thread. Returns whether any was scanned.
+    async fn discover_tests(&mut self, event: Option<&Event>) -> bool {
+        let mut targets = self.project.test_targets();
+        match event {
+            Some(event) if event.path().extension().map_or(false, |ext| ext == "swift") => {
+                targets.retain(|_, sources| sources.iter().any(|s| event.path().starts_with(s)))
+            }
+            Some(_) => return false,
+            None => self.tests.clear(),
+        };
+        if targets.is_empty() {
+            return false;
+        }
+
+        let discover = move || {
+            targets
+                .into_iter()
+                .map(|(name, sources)| (name, project::discover_tests(&sources)))
+                .collect::<Vec<_>>()
+        };
+        match tokio::task::spawn_blocking(discover).await {
+            Ok(tests) => self.tests.extend(tests),
+            Err(err) => tracing::error!("[{}] Failed to discover tests: {err}", self.name),
+        };
+        true
+    }
+
+    /// Restore watch subscriptions persisted before the daemon restarted
+    async fn restore_watchers(&mut self) {
+        let watchers = state::watchers(self.project.root());
+        state::save(self.project.root(), vec![]);
+        if watchers.is_empty() {
+            return;
+        }
+
+        let msg = format!("[{}] Restoring {} watcher(s)", self.name, watchers.len());
+        self.broadcaster.info(msg);
+        for watcher in watchers {
+            match watcher {
+                WatchState::Build(req) => self.on_build(req).await,
+                WatchState::Run(req) => self.on_run(req).await,
+            }
+        }
+        self.set_client_project_state(None);
+    }
+
+    /// Track watch subscription changes in daemon state
+    fn persist_watcher(&mut self, key: String, watcher: Option<WatchState>) {
+        self.watchers.retain(|w| w.key() != key);
+        self.watchers.extend(watcher);
+        state::save(self.project.root(), self.watchers.clone());
+    }
+
+    /// Notify clients, stop runners, watchers and running builds
+    async fn on_shutdown(&mut self) {
+        info!("Shutting down");
+        self.broadcaster
+            .warn(format!("[{}] Daemon shutting down", self.name));
+        self.watcher_subscribers.clear().await;
+        for (_, runner) in self.runners.drain() {
+            runner.discard().await;
+        }
+        self.abort.notify_waiters();
+        self.broadcaster.shutdown().await;
+    }
+
+    fn on_status(&self, tx: oneshot::Sender<RuntimeInfo>) {
+        tx.send(RuntimeInfo {
+            kind: self.project.kind(),
+            watchlist: self.watcher_subscribers.keys(),
+            tasks: self.broadcaster.tasks().list(),
+            listeners: self.broadcaster.listener_stats(),
+        })
+        .ok();
+    }
+
     fn set_client_project_state(&mut self, id: Option<u32>) {
         let info = ProjectInfo {
             watchlist: self.watcher_subscribers.keys(),
             targets: self.project.targets().clone(),
+            tests: self.tests.clone(),
         };
         self.broadcaster.set_state(id, State::ProjectInfo(info))
     }
@@ -0,0 +1,114 @@
+use crate::server::{BuildRequest, RunRequest};
+use crate::Result;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Daemon state, loaded from the state file on first access
+static STATE: Lazy<Mutex<DaemonState>> =
+    Lazy::new(|| Mutex::new(DaemonState::read(&crate::config::get().state_path)));
+
+/// Watch subscription persisted across daemon restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchState {
+    Build(BuildRequest),
+    Run(RunRequest),
+}
+
+/// Registered project roots with their watch subscriptions
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DaemonState {
+    projects: BTreeMap<PathBuf, Vec<WatchState>>,
+}
+
+impl WatchState {
+    /// Key the subscription is registered with in WatchSubscribers
+    pub fn key(&self) -> String {
+        match self {
+            Self::Build(req) => req.to_string(),
+            Self::Run(req) => req.to_string(),
+        }
+    }
+}
+
+impl DaemonState {
+    fn read(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        std::fs::read_to_string(&path)
+            .map_err(crate::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+            .unwrap_or_else(|err| {
+                tracing::error!("Failed to read {path:?}, ignoring: {err}");
+                Self::default()
+            })
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        crate::config::set_private_permissions(path)
+    }
+}
+
+fn update(f: impl FnOnce(&mut DaemonState)) {
+    let mut state = STATE.lock().unwrap();
+    f(&mut state);
+    if let Err(err) = state.write(&crate::config::get().state_path) {
+        tracing::error!("Failed to persist daemon state: {err}");
+    }
+}
+
+/// Get persisted watch subscriptions of a given root
+pub fn watchers(root: &Path) -> Vec<WatchState> {
+    let state = STATE.lock().unwrap();
+    state.projects.get(root).cloned().unwrap_or_default()
+}
+
+/// Persist root and its watch subscriptions
+pub fn save(root: &Path, watchers: Vec<WatchState>) {
+    update(|state| {
+        state.projects.insert(root.to_path_buf(), watchers);
+    })
+}
+
+/// Forget root and its watch subscriptions
+pub fn remove(root: &Path) {
+    update(|state| {
+        state.projects.remove(root);
+    })
+}
+
+#[test]
+fn test_state_round_trip() {
+    use crate::{BuildSettings, Operation};
+    let path = std::env::temp_dir().join(format!("xbase-state-{}.json", std::process::id()));
+    let root = PathBuf::from("/tmp/App");
+    let request = BuildRequest {
+        root: root.clone(),
+        settings: BuildSettings {
+            target: "App".into(),
+            configuration: "Debug".into(),
+            scheme: None,
+            sarif: None,
+            junit: None,
+        },
+        operation: Operation::Watch,
+    };
+    let watcher = WatchState::Build(request);
+    let key = watcher.key();
+
+    let mut state = DaemonState::default();
+    state.projects.insert(root.clone(), vec![watcher]);
+    state.write(&path).unwrap();
+    let state = DaemonState::read(&path);
+    assert_eq!(state.projects[&root].len(), 1);
+    assert_eq!(state.projects[&root][0].key(), key);
+
+    std::fs::write(&path, "{ corrupted").unwrap();
+    assert!(DaemonState::read(&path).projects.is_empty());
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+/// How many times to look for the runtime of a project that was just registered
+const ATTACH_RETRIES: usize = 5;
+
+/// Turn the connection into a broadcast listener of a registered project.
+///
+/// For clients that can't reach broadcast unix socket, e.g. remote clients connected over tcp.
+/// Once the request succeeds, no more requests are read and only broadcast messages are written.
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct AttachRequest {
+    pub id: u32,
+    pub root: PathBuf,
+    /// Shared secret required from clients connected over tcp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Token returned by the register request
+    #[serde(default)]
+    pub broadcast_token: String,
+    /// Broadcast messages to receive
+    #[serde(default)]
+    pub subscription: Subscription,
+}
+
+impl AttachRequest {
+    /// Wait for the client to be registered with the project runtime.
+    ///
+    /// Registration is deferred by RegisterRequest, so an attach request right after
+    /// registering might arrive before the client is connected.
+    pub(crate) async fn ensure_registered(&self) -> Result<()> {
+        for _ in 0..ATTACH_RETRIES {
+            if let Some(runtime) = runtimes().await.get(&self.root) {
+                if runtime.contains(&self.id) {
+                    if !runtime.verify_token(self.id, &self.broadcast_token) {
+                        return Err(Error::Unauthorized(format!(
+                            "unknown client {} or invalid token",
+                            self.id
+                        )));
+                    }
+                    return Ok(());
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Err(Error::UnknownProject(self.root.clone()))
+    }
+
+    /// Attach stream to project broadcaster
+    pub(crate) async fn attach(self, stream: ListenerStream) {
+        match runtimes().await.get(&self.root) {
+            Some(runtime) => runtime.send(PRMessage::Attach(self.id, stream, self.subscription)),
+            None => tracing::error!("[{}] Runtime dropped before attaching", self.id),
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler<()> for AttachRequest {
+    async fn handle(self) -> Result<()> {
+        Err(Error::Unexpected(
+            "Attach request must be handled by the connection".into(),
+        ))
+    }
+}
@@ -1,11 +1,12 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
-use std::{path::PathBuf, sync::Arc};
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
 use {super::*, crate::*};
 
 /// Request to build a particular project
-#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
 pub struct BuildRequest {
     pub root: PathBuf,
     pub settings: BuildSettings,
@@ -30,10 +31,32 @@ impl Display for BuildRequest {
     }
 }
 
+impl BuildRequest {
+    pub fn into_service(self, broadcast: &Arc<Broadcast>) -> BuildService {
+        BuildService {
+            request: self,
+            broadcast: Arc::downgrade(broadcast),
+        }
+    }
+}
+
+/// Build Service
+pub struct BuildService {
+    pub request: BuildRequest,
+    /// Broadcast builds are ran through, to cancel them once discarded
+    broadcast: Weak<Broadcast>,
+}
+
+impl Display for BuildService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.request)
+    }
+}
+
 #[async_trait]
-impl Watchable for BuildRequest {
+impl Watchable for BuildService {
     async fn trigger(&self, p: &mut ProjectImpl, _: &Event, b: &Arc<Broadcast>) -> Result<()> {
-        p.build(&self.settings, None, b)?;
+        p.build(&self.request.settings, None, b)?;
         Ok(())
     }
 
@@ -47,6 +70,10 @@ impl Watchable for BuildRequest {
         false
     }
 
-    /// Drop watchable for watching a given file system
-    async fn discard(&self) {}
+    /// Drop watchable for watching a given file system, aborting the build under progress
+    async fn discard(&self) {
+        if let Some(broadcast) = self.broadcast.upgrade() {
+            broadcast.cancel_tasks(&TaskKind::Build, &self.request.settings.target);
+        }
+    }
 }
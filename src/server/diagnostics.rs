@@ -0,0 +1,27 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Get diagnostics of the last build of each target of a registered project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct GetDiagnosticsRequest {
+    pub root: PathBuf,
+    /// Only get diagnostics about a given file, relative to root or absolute
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+}
+
+#[async_trait]
+impl RequestHandler<HashMap<String, Vec<Diagnostic>>> for GetDiagnosticsRequest {
+    async fn handle(self) -> Result<HashMap<String, Vec<Diagnostic>>> {
+        let file = self.file.as_ref().map(|file| self.root.join(file));
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.diagnostics().get(file.as_deref()))
+    }
+}
@@ -7,6 +7,8 @@ use std::path::PathBuf;
 /// Drop a given set of roots to be dropped (i.e. unregistered)
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
 pub struct DropRequest {
+    /// Client id, assigned by the daemon and ignored if sent by the client
+    #[serde(default)]
     pub id: u32,
     pub roots: Vec<PathBuf>,
 }
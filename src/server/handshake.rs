@@ -0,0 +1,105 @@
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+/// Protocol version spoken by this daemon
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest client protocol version this daemon can still serve
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+/// Request methods this daemon can process
+pub const REQUEST_METHODS: &[&str] = &[
+    "register",
+    "build",
+    "run",
+    "drop",
+    "status",
+    "shutdown",
+    "attach",
+    "tasks",
+    "get_diagnostics",
+    "test",
+];
+
+/// Message kinds this daemon broadcast to clients
+pub const MESSAGE_KINDS: &[&str] = &[
+    "Notify",
+    "Log",
+    "OpenLogger",
+    "ReloadLspServer",
+    "SetCurrentTask",
+    "UpdateCurrentTask",
+    "TaskProgress",
+    "Diagnostic",
+    "TestSuite",
+    "TestCase",
+    "FinishCurrentTask",
+    "SetWatching",
+    "SetState",
+];
+
+/// Client information sent on registration
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct ClientInfo {
+    /// Client name, e.g. nvim or vscode
+    pub name: String,
+    /// Protocol version the client speaks
+    pub protocol: u32,
+    /// Message kinds the client is able to process
+    #[serde(default)]
+    pub messages: Vec<String>,
+}
+
+/// Daemon information returned on registration
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct DaemonInfo {
+    /// Daemon version
+    pub version: String,
+    /// Protocol version the daemon speaks
+    pub protocol: u32,
+    /// Request methods the daemon can process
+    pub methods: Vec<String>,
+    /// Message kinds the daemon broadcast
+    pub messages: Vec<String>,
+}
+
+impl Default for DaemonInfo {
+    fn default() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            protocol: PROTOCOL_VERSION,
+            methods: REQUEST_METHODS.iter().map(ToString::to_string).collect(),
+            messages: MESSAGE_KINDS.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl ClientInfo {
+    /// Ensure the client speaks a protocol version this daemon can serve
+    pub fn ensure_compatible(client: Option<&Self>) -> Result<()> {
+        let client = client.ok_or_else(|| {
+            Error::Incompatible(format!(
+                "client didn't send protocol information, daemon speaks protocol {PROTOCOL_VERSION}"
+            ))
+        })?;
+
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&client.protocol) {
+            return Err(Error::Incompatible(format!(
+                "{} speaks protocol {}, daemon supports {MIN_PROTOCOL_VERSION} to {PROTOCOL_VERSION}",
+                client.name, client.protocol
+            )));
+        }
+
+        let unsupported = MESSAGE_KINDS
+            .iter()
+            .filter(|kind| !client.messages.iter().any(|m| m == *kind))
+            .collect::<Vec<_>>();
+
+        if !client.messages.is_empty() && !unsupported.is_empty() {
+            tracing::warn!("{} doesn't support {unsupported:?} messages", client.name);
+        }
+
+        Ok(())
+    }
+}
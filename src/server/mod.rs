@@ -1,78 +1,251 @@
+mod attach;
 mod build;
+mod diagnostics;
 mod drop;
+mod handshake;
 mod register;
 mod request;
 mod response;
 mod run;
+mod session;
+mod shutdown;
+mod status;
+mod tasks;
+mod test;
 
-use std::os::unix::prelude::AsRawFd;
-use tokio::net::unix::{ReadHalf, WriteHalf};
-use tokio_serde::{formats::*, SymmetricallyFramed};
-use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite};
-use tracing::instrument;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf};
+use tokio::sync::mpsc;
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec, LinesCodecError};
+use tracing::{instrument, Instrument};
 use typescript_type_def::TypeDef;
 
-pub use {build::*, drop::*, register::*, request::*, response::*, run::*};
+pub use {
+    attach::*, build::*, diagnostics::*, drop::*, handshake::*, register::*, request::*,
+    response::*, run::*, session::*, shutdown::*, status::*, tasks::*, test::*,
+};
 
-/// Stream of Requests to read Requests from
+/// Maximum length of a single request line
+const MAX_REQUEST_LENGTH: usize = 1024 * 1024;
+
+/// Number of consecutive malformed requests after which the connection is closed
+const MAX_DECODE_FAILURES: usize = 3;
+
+/// Stream of newline delimited Requests to read Requests from
 struct RequestStream;
 
-/// Stream of Responses to write response to
+/// Stream of newline delimited Responses to write response to
 struct ResponseStream;
 
+/// Transport a client is connected through
+#[derive(Debug, Clone, Copy)]
+pub enum Transport {
+    /// Local unix socket with its file descriptor
+    Unix(i32),
+    /// Tcp connection with the peer address
+    Tcp(SocketAddr),
+}
+
+impl Transport {
+    /// Whether clients connected through this transport must provide a token
+    pub fn requires_token(&self) -> bool {
+        matches!(self, Self::Tcp(_))
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unix(fd) => write!(f, "unix:{fd}"),
+            Self::Tcp(addr) => write!(f, "tcp:{addr}"),
+        }
+    }
+}
+
 /// Trait that must be implemented by All Request members
 #[async_trait::async_trait]
 pub trait RequestHandler<T: serde::Serialize> {
     async fn handle(self) -> crate::Result<T>;
 }
 
-/// Generic over T and Framed Write/Read
-type F<T, F> = SymmetricallyFramed<F, T, SymmetricalJson<T>>;
-
 impl RequestStream {
-    fn new<'a>(r: ReadHalf<'a>) -> F<Request, FramedRead<ReadHalf<'a>, BytesCodec>> {
-        let transport = FramedRead::new(r, BytesCodec::default());
-        F::new(transport, SymmetricalJson::default())
+    fn new<S: AsyncRead>(r: ReadHalf<S>) -> FramedRead<ReadHalf<S>, LinesCodec> {
+        FramedRead::new(r, LinesCodec::new_with_max_length(MAX_REQUEST_LENGTH))
+    }
+
+    /// Decode a request line, on failure return an error response with the request id if any
+    fn decode(line: &str) -> Result<Request, Response> {
+        serde_json::from_str::<Request>(line).map_err(|err| {
+            let id = serde_json::from_str::<serde_json::Value>(line)
+                .ok()
+                .and_then(|v| v.get("id")?.as_u64())
+                .map(|id| id as u32);
+            let error = crate::Error::RequestParse(err.to_string());
+            Response::new::<()>(Err(error)).with_id(id)
+        })
     }
 }
 
 impl ResponseStream {
-    fn new<'a>(w: WriteHalf<'a>) -> F<Response, FramedWrite<WriteHalf<'a>, BytesCodec>> {
-        let transport = FramedWrite::new(w, BytesCodec::default());
-        F::new(transport, SymmetricalJson::default())
+    fn new<S: AsyncWrite>(w: WriteHalf<S>) -> FramedWrite<WriteHalf<S>, LinesCodec> {
+        FramedWrite::new(w, LinesCodec::new())
+    }
+
+    /// Encode response as a single json line
+    fn encode(response: &Response) -> String {
+        serde_json::to_string(response).unwrap()
     }
 }
 
 /// Future that await and process client requests.
-#[instrument(parent = None, name = "Client", skip_all, fields(fd = stream.as_raw_fd()))]
-pub async fn handle(mut stream: tokio::net::UnixStream) {
-    use futures::{SinkExt, TryStreamExt};
-    use tracing::{error, info};
+///
+/// Requests are newline delimited json objects. They are processed concurrently, responses
+/// are written back as soon as they are ready and carry the id of the request they are for.
+///
+/// Malformed requests get an error response, and the connection is closed after
+/// [`MAX_DECODE_FAILURES`] consecutive failures. Once the client closes its write half, pending
+/// requests are still answered before the connection is closed.
+///
+/// Clients connected over tcp must provide the configured token with their first
+/// `Register` or `Attach` request, otherwise the connection is closed.
+#[instrument(parent = None, name = "Client", skip_all, fields(peer = %transport))]
+pub async fn handle<S>(stream: S, transport: Transport)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    use futures::{SinkExt, StreamExt};
+    use tracing::{error, info, warn};
     info!("Connected");
 
-    // Client Registered roots
-    let mut roots = vec![];
-    let (reader, writer) = stream.split();
+    // Client session, created on first registration
+    let mut session = None;
+    let (reader, writer) = tokio::io::split(stream);
     let (mut reader, mut writer) = (RequestStream::new(reader), ResponseStream::new(writer));
-    let mut id = 0;
+    let (tx, mut rx) = mpsc::unbounded_channel::<Response>();
+    let mut failures = 0;
+    let mut authorized = !transport.requires_token();
 
     loop {
-        match reader.try_next().await {
-            Ok(Some(request)) => {
-                if let Request::Register(r) = &request {
-                    id = r.id;
-                    roots.push(r.root.clone())
-                };
-                let response = request.handle().await;
-                let send_res = writer.send(response).await;
+        tokio::select! {
+            Some(response) = rx.recv() => {
+                let send_res = writer.send(ResponseStream::encode(&response)).await;
                 send_res.map_err(|err| error!("Send Error: {err}")).ok();
             }
-            Err(err) => error!("Read Error: {err:#?}"),
-            Ok(None) => {
-                Request::Drop(DropRequest { id, roots }).handle().await;
-                break;
+            result = reader.next() => {
+                let decoded = match result {
+                    Some(Ok(line)) if line.trim().is_empty() => continue,
+                    Some(Ok(line)) => RequestStream::decode(&line),
+                    Some(Err(LinesCodecError::MaxLineLengthExceeded)) => {
+                        let error = crate::Error::RequestParse("Request too long".into());
+                        Err(Response::new::<()>(Err(error)))
+                    }
+                    Some(Err(LinesCodecError::Io(err))) => {
+                        error!("Read Error: {err}");
+                        break;
+                    }
+                    None => {
+                        // Write responses of requests still being handled before closing
+                        drop(tx);
+                        while let Some(response) = rx.recv().await {
+                            let send_res = writer.send(ResponseStream::encode(&response)).await;
+                            send_res.map_err(|err| error!("Send Error: {err}")).ok();
+                        }
+                        break;
+                    }
+                };
+
+                match decoded {
+                    Ok(request) => {
+                        failures = 0;
+                        if !authorized {
+                            authorized = crate::config::get().verify_tcp_token(request.kind.token());
+                            if !authorized {
+                                warn!("Unauthorized, closing connection");
+                                let error = crate::Error::Unauthorized("invalid or missing token".into());
+                                let response = Response::new::<()>(Err(error)).with_id(request.id);
+                                writer.send(ResponseStream::encode(&response)).await.ok();
+                                break;
+                            }
+                        }
+                        match request.kind {
+                            RequestKind::Attach(req) => {
+                                let registered = req.ensure_registered().await;
+                                let attached = registered.is_ok();
+                                let response = Response::new(registered).with_id(request.id);
+                                let send_res = writer.send(ResponseStream::encode(&response)).await;
+                                send_res.map_err(|err| error!("Send Error: {err}")).ok();
+                                if attached {
+                                    req.attach(ListenerStream::new(writer.into_inner())).await;
+                                    break;
+                                }
+                            }
+                            kind => {
+                                let kind = with_session(kind, &mut session).await;
+                                let (tx, request) = (tx.clone(), Request { id: request.id, kind });
+                                tokio::spawn(
+                                    async move { tx.send(request.handle().await).ok(); }.in_current_span(),
+                                );
+                            }
+                        }
+                    }
+                    Err(response) => {
+                        failures += 1;
+                        warn!("Malformed Request [{failures}/{MAX_DECODE_FAILURES}]");
+                        let send_res = writer.send(ResponseStream::encode(&response)).await;
+                        send_res.map_err(|err| error!("Send Error: {err}")).ok();
+                        if failures >= MAX_DECODE_FAILURES {
+                            error!("Too many malformed requests, closing connection");
+                            break;
+                        }
+                    }
+                }
             }
         }
     }
+
+    if let Some(session) = session {
+        session.detach().await;
+    }
+
     info!("Disconnected");
 }
+
+/// Set daemon assigned client id on requests and track roots registered by the client.
+///
+/// The session is created, or resumed if the client provides a session token, on the first
+/// registration.
+async fn with_session(kind: RequestKind, session: &mut Option<Session>) -> RequestKind {
+    match kind {
+        RequestKind::Register(mut req) => {
+            if session.is_none() {
+                let resumed = match req.session.as_deref() {
+                    Some(token) => Session::resume(token).await,
+                    None => None,
+                };
+                *session = Some(match resumed {
+                    Some(resumed) => resumed,
+                    None => Session::new().await,
+                });
+            }
+            let session = session.as_mut().unwrap();
+            req.id = session.id;
+            req.session = Some(session.token.clone());
+            if !session.roots.contains(&req.root) {
+                session.roots.push(req.root.clone());
+            }
+            RequestKind::Register(req)
+        }
+        RequestKind::Drop(mut req) => {
+            match session {
+                Some(session) => {
+                    req.id = session.id;
+                    session.roots.retain(|root| !req.roots.contains(root));
+                }
+                None => req.roots.clear(),
+            };
+            RequestKind::Drop(req)
+        }
+        kind => kind,
+    }
+}
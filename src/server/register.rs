@@ -1,5 +1,5 @@
 use super::*;
-use crate::runtime::ProjectRuntime;
+use crate::runtime::{PRMessage, ProjectRuntime};
 use crate::*;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -8,42 +8,85 @@ use std::{path::PathBuf, time::Duration};
 /// Register a project root
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
 pub struct RegisterRequest {
+    /// Client id, assigned by the daemon and ignored if sent by the client
+    #[serde(default)]
     pub id: u32,
     pub root: PathBuf,
+    /// Client information used to ensure client/daemon compatibility
+    #[serde(default)]
+    pub client: Option<ClientInfo>,
+    /// Shared secret required from clients connected over tcp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Session token returned by a previous registration, used to resume the session
+    /// after reconnecting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+}
+
+/// Registration result
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct RegisterResponse {
+    /// Client id assigned by the daemon, to be sent to the broadcaster
+    pub id: u32,
+    /// Session token to resume the session after reconnecting
+    pub session: String,
+    /// Broadcaster address to read messages from
+    pub address: PathBuf,
+    /// Token to send to the broadcaster after the client id
+    pub broadcast_token: String,
+    /// Daemon information
+    pub daemon: DaemonInfo,
 }
 
 #[async_trait]
-impl RequestHandler<PathBuf> for RegisterRequest {
-    async fn handle(self) -> Result<PathBuf> {
-        let RegisterRequest { id, root } = self;
+impl RequestHandler<RegisterResponse> for RegisterRequest {
+    async fn handle(self) -> Result<RegisterResponse> {
+        let RegisterRequest {
+            id,
+            root,
+            client,
+            session,
+            ..
+        } = self;
+        let session = session.unwrap_or_default();
+        ClientInfo::ensure_compatible(client.as_ref())?;
+        if let Some(client) = client {
+            tracing::info!("[{id}] {} (protocol {})", client.name, client.protocol);
+        }
+
         let mut runtimes = runtimes().await;
         tracing::trace!("{:#?}", runtimes);
 
         if let Some(runtime) = runtimes.get_mut(&root) {
-            if runtime.contains(&id) {
-                return Err(Error::Unexpected(
-                    "Trying to adding a connected client!".into(),
-                ));
-            }
-
             let address = runtime.broadcaster_adderss().clone();
+            let broadcast_token = runtime.issue_token(id);
             tokio::spawn(async move {
                 tokio::time::sleep(Duration::from_millis(100)).await;
-                runtimes.get_mut(&root).unwrap().connect(id);
+                let runtime = runtimes.get_mut(&root).unwrap();
+                if runtime.contains(&id) {
+                    // Resumed session, resend state to the new broadcast listener
+                    runtime.send(PRMessage::Resume(id));
+                } else {
+                    runtime.connect(id);
+                }
             });
 
-            return Ok(address);
+            return Ok(RegisterResponse::new(id, session, address, broadcast_token));
         }
 
         let (rloop, mut runtime) = match ProjectRuntime::new(root.clone()).await {
             Ok(v) => v,
             Err(err) => {
-                let name = root.as_path().name().unwrap();
-                return Err(Error::Setup(name, err.to_string()));
+                return Err(Error::Setup {
+                    project: root.as_path().name().unwrap(),
+                    msg: err.to_string(),
+                });
             }
         };
 
         let address = runtime.broadcaster_adderss().clone();
+        let broadcast_token = runtime.issue_token(id);
 
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_millis(100)).await;
@@ -53,6 +96,18 @@ impl RequestHandler<PathBuf> for RegisterRequest {
             rloop.start(id).await;
         });
 
-        Ok(address)
+        Ok(RegisterResponse::new(id, session, address, broadcast_token))
+    }
+}
+
+impl RegisterResponse {
+    fn new(id: u32, session: String, address: PathBuf, broadcast_token: String) -> Self {
+        Self {
+            id,
+            session,
+            address,
+            broadcast_token,
+            daemon: DaemonInfo::default(),
+        }
     }
 }
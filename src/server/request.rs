@@ -2,10 +2,21 @@ use super::*;
 use serde::{Deserialize, Serialize};
 use tap::Pipe;
 
+/// Request clients can make, with an optional id echoed back in the response
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct Request {
+    /// Client supplied id used to match a response to its request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    /// Request method and arguments
+    #[serde(flatten)]
+    pub kind: RequestKind,
+}
+
 /// Requests clinets can make
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
 #[serde(tag = "method", content = "args", rename_all = "snake_case")]
-pub enum Request {
+pub enum RequestKind {
     /// Register project root and get broadcaster reader file description
     Register(RegisterRequest),
     /// Build Project and get path to where to build log will be located
@@ -14,15 +25,52 @@ pub enum Request {
     Run(RunRequest),
     /// Drop projects at a given roots
     Drop(DropRequest),
+    /// Get status of registered project runtimes
+    Status(StatusRequest),
+    /// Stop all project runtimes and shutdown the daemon
+    Shutdown(ShutdownRequest),
+    /// Receive project broadcast messages through the current connection
+    Attach(AttachRequest),
+    /// Get tasks under progress of a registered project
+    Tasks(TasksRequest),
+    /// Get diagnostics of the last build of each target of a registered project
+    GetDiagnostics(GetDiagnosticsRequest),
+    /// Build and run tests of a test target, streaming test suites and cases results
+    Test(TestRequest),
 }
 
 impl Request {
     pub async fn handle(self) -> Response {
+        let Self { id, kind } = self;
+        match kind {
+            RequestKind::Register(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Build(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Run(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Drop(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Status(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Shutdown(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Attach(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Tasks(req) => req.handle().await.pipe(Response::new),
+            RequestKind::GetDiagnostics(req) => req.handle().await.pipe(Response::new),
+            RequestKind::Test(req) => req.handle().await.pipe(Response::new),
+        }
+        .with_id(id)
+    }
+}
+
+impl RequestKind {
+    /// Token provided by the request, if any
+    pub fn token(&self) -> Option<&str> {
         match self {
-            Request::Register(req) => req.handle().await.pipe(Response::new),
-            Request::Build(req) => req.handle().await.pipe(Response::new),
-            Request::Run(req) => req.handle().await.pipe(Response::new),
-            Request::Drop(req) => req.handle().await.pipe(Response::new),
+            Self::Register(req) => req.token.as_deref(),
+            Self::Attach(req) => req.token.as_deref(),
+            _ => None,
         }
     }
 }
+
+impl From<RequestKind> for Request {
+    fn from(kind: RequestKind) -> Self {
+        Self { id: None, kind }
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{types::Result, ServerError};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use typescript_type_def::TypeDef;
 
 /// Server Response
-#[derive(Default, Debug, Serialize, TypeDef)]
+#[derive(Default, Debug, Serialize, Deserialize, TypeDef)]
 pub struct Response {
+    /// Id of the request this response is for, if the request had one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,4 +24,24 @@ impl Response {
         };
         response
     }
+
+    /// Set the id of the request this response is for
+    pub fn with_id(mut self, id: Option<u32>) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Get the id of the request this response is for
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    /// Convert response to the result of the request
+    pub fn into_result<T: DeserializeOwned>(self) -> Result<T> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        let data = self.data.unwrap_or_default();
+        serde_json::from_value(data).map_err(|e| crate::Error::Unexpected(e.to_string()))
+    }
 }
@@ -7,7 +7,7 @@ use std::fmt::{self, Display};
 use std::path::PathBuf;
 
 /// Request to Run a particular project.
-#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
 pub struct RunRequest {
     pub root: PathBuf,
     pub settings: BuildSettings,
@@ -0,0 +1,147 @@
+use super::*;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How long a disconnected client session is kept before its roots are dropped
+pub const SESSION_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Next client id to assign
+static NEXT_CLIENT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Counter used to invalidate pending drops of resumed sessions
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Sessions indexed by their token
+static SESSIONS: Lazy<Mutex<HashMap<String, SessionEntry>>> = Lazy::new(Default::default);
+
+#[derive(Debug)]
+struct SessionEntry {
+    id: u32,
+    roots: Vec<PathBuf>,
+    /// Generation of the last connection that owned the session
+    generation: u64,
+    /// Whether the session is owned by a connection
+    attached: bool,
+}
+
+/// Client identity assigned by the daemon to a connection
+#[derive(Debug)]
+pub struct Session {
+    /// Client id used to identify the client in project runtimes and broadcasters
+    pub id: u32,
+    /// Secret the client can use to resume the session after reconnecting
+    pub token: String,
+    /// Roots registered by the client
+    pub roots: Vec<PathBuf>,
+    generation: u64,
+}
+
+impl Session {
+    /// Create a new session with a fresh client id
+    pub async fn new() -> Self {
+        let session = Self {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            token: crate::util::token::new_token(),
+            roots: vec![],
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+        };
+        SESSIONS.lock().await.insert(
+            session.token.clone(),
+            SessionEntry {
+                id: session.id,
+                roots: vec![],
+                generation: session.generation,
+                attached: true,
+            },
+        );
+        session
+    }
+
+    /// Resume a detached session, returns None if the session expired or is in use
+    pub async fn resume(token: &str) -> Option<Self> {
+        let mut sessions = SESSIONS.lock().await;
+        let entry = sessions.get_mut(token).filter(|entry| !entry.attached)?;
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        entry.generation = generation;
+        entry.attached = true;
+        tracing::info!("[{}] Session resumed", entry.id);
+        Some(Self {
+            id: entry.id,
+            token: token.to_string(),
+            roots: entry.roots.clone(),
+            generation,
+        })
+    }
+
+    /// Detach session from its connection and drop its roots unless resumed within
+    /// [`SESSION_GRACE_PERIOD`]
+    pub async fn detach(self) {
+        self.detach_for(SESSION_GRACE_PERIOD).await
+    }
+
+    /// Detach session from its connection and drop its roots unless resumed within a grace period
+    async fn detach_for(self, grace_period: Duration) {
+        let Self {
+            id,
+            token,
+            roots,
+            generation,
+        } = self;
+
+        match SESSIONS.lock().await.get_mut(&token) {
+            Some(entry) if entry.generation == generation => {
+                entry.attached = false;
+                entry.roots = roots;
+            }
+            _ => return,
+        };
+
+        tokio::spawn(async move {
+            tokio::time::sleep(grace_period).await;
+            let roots = {
+                let mut sessions = SESSIONS.lock().await;
+                match sessions.get(&token) {
+                    Some(entry) if entry.generation == generation && !entry.attached => {
+                        sessions.remove(&token).unwrap().roots
+                    }
+                    _ => return,
+                }
+            };
+            tracing::info!("[{id}] Session expired");
+            Request::from(RequestKind::Drop(DropRequest { id, roots }))
+                .handle()
+                .await;
+        });
+    }
+}
+
+#[tokio::test]
+async fn test_session_resume_within_grace_period() {
+    let session = Session::new().await;
+    let (id, token) = (session.id, session.token.clone());
+    assert!(Session::resume(&token).await.is_none(), "attached session");
+
+    session.detach_for(Duration::from_millis(50)).await;
+    let session = Session::resume(&token).await.unwrap();
+    assert_eq!(session.id, id);
+
+    // Expiry of the first detach must not drop the resumed and detached again session
+    session.detach_for(Duration::from_secs(5)).await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(Session::resume(&token).await.unwrap().id, id);
+}
+
+#[tokio::test]
+async fn test_session_expire_after_grace_period() {
+    let session = Session::new().await;
+    let token = session.token.clone();
+
+    session.detach_for(Duration::from_millis(20)).await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(Session::resume(&token).await.is_none());
+    assert!(!SESSIONS.lock().await.contains_key(&token));
+}
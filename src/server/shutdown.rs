@@ -0,0 +1,25 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Shutdown the daemon after stopping and dropping all project runtimes
+#[derive(Debug, Default, Serialize, Deserialize, TypeDef)]
+pub struct ShutdownRequest {}
+
+#[async_trait]
+impl RequestHandler<()> for ShutdownRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::info!("Shutdown requested");
+        shutdown_runtimes().await;
+
+        // Give the response a chance to be written before the server stops
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            request_shutdown();
+        });
+
+        Ok(())
+    }
+}
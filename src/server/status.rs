@@ -0,0 +1,69 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+/// How long to wait for a runtime to report its information
+const RUNTIME_STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Get status of registered project runtimes, optionally only for a given root
+#[derive(Debug, Default, Serialize, Deserialize, TypeDef)]
+pub struct StatusRequest {
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+}
+
+/// Status of a registered project runtime
+#[derive(Debug, Serialize, TypeDef)]
+pub struct RuntimeStatus {
+    /// Project root
+    pub root: PathBuf,
+    /// Project name
+    pub name: String,
+    /// Connected clients ids
+    pub clients: Vec<u32>,
+    /// Message broadcaster address
+    pub broadcaster: PathBuf,
+    /// Information reported by the runtime, none if it didn't respond in time
+    pub runtime: Option<RuntimeInfo>,
+}
+
+#[async_trait]
+impl RequestHandler<Vec<RuntimeStatus>> for StatusRequest {
+    async fn handle(self) -> Result<Vec<RuntimeStatus>> {
+        let runtimes = runtimes().await;
+        let pending = runtimes
+            .values()
+            .filter(|r| {
+                self.root
+                    .as_ref()
+                    .map(|root| root == r.root())
+                    .unwrap_or(true)
+            })
+            .map(|r| {
+                let status = RuntimeStatus {
+                    root: r.root().clone(),
+                    name: r.name(),
+                    clients: r.clients(),
+                    broadcaster: r.broadcaster_adderss().clone(),
+                    runtime: None,
+                };
+                (status, r.status())
+            })
+            .collect::<Vec<_>>();
+        // Don't hold runtimes while waiting on them, runtimes may need it to process messages
+        drop(runtimes);
+
+        let statuses = pending.into_iter().map(|(mut status, info)| async move {
+            match tokio::time::timeout(RUNTIME_STATUS_TIMEOUT, info).await {
+                Ok(Ok(info)) => status.runtime = Some(info),
+                _ => tracing::warn!("[{}] runtime didn't report its status", status.name),
+            };
+            status
+        });
+        let statuses = futures::future::join_all(statuses).await;
+
+        Ok(statuses)
+    }
+}
@@ -0,0 +1,22 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Get tasks under progress of a registered project, oldest first
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct TasksRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<Vec<TaskInfo>> for TasksRequest {
+    async fn handle(self) -> Result<Vec<TaskInfo>> {
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.tasks())
+    }
+}
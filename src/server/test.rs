@@ -0,0 +1,32 @@
+use super::*;
+use crate::runtime::PRMessage;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to build and run tests of a test target
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct TestRequest {
+    pub root: PathBuf,
+    /// Settings with the test target to build and run
+    pub settings: BuildSettings,
+    /// Only run tests matching `Class` or `Class/testName`
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Simulator to run tests on, macOS if none
+    #[serde(default)]
+    pub device: Option<DeviceLookup>,
+}
+
+#[async_trait]
+impl RequestHandler<()> for TestRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
+            .map(|r| r.send(PRMessage::Test(self)))
+    }
+}
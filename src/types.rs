@@ -1,6 +1,6 @@
 use crate::error::*;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display, path::PathBuf};
 use strum::{Display as EnumDisplay, EnumString};
 use typescript_type_def::TypeDef;
 use xcodeproj::pbxproj::PBXTargetInfo;
@@ -13,6 +13,24 @@ pub struct ProjectInfo {
     pub watchlist: Vec<String>,
     /// Get targets information for a registers project with a given root
     pub targets: HashMap<String, TargetInfo>,
+    /// Test targets with the test cases discovered in their sources
+    pub tests: HashMap<String, Vec<TestCaseInfo>>,
+}
+
+/// Kind of project a runtime is backed by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, EnumDisplay, TypeDef)]
+pub enum ProjectKind {
+    /// Project generated by xcodegen (project.yml)
+    XCodeGen,
+    /// Project generated by tuist (Project.swift)
+    Tuist,
+    /// Swift Package (Package.swift)
+    Swift,
+    /// Plain xcodeproj
+    Barebone,
+    /// Scripted project used in tests
+    #[cfg(feature = "harness")]
+    Fake,
 }
 
 /// Type of operation for building/ruuning a target/scheme
@@ -32,6 +50,12 @@ pub struct BuildSettings {
     pub configuration: String,
     /// Scheme to build with
     pub scheme: Option<String>,
+    /// Path to write a SARIF report of the build diagnostics to, relative to project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sarif: Option<PathBuf>,
+    /// Path to write a JUnit report of the build outcome to, relative to project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub junit: Option<PathBuf>,
 }
 
 /// Target specfic information
@@ -41,6 +65,19 @@ pub struct TargetInfo {
     pub configurations: Vec<String>,
 }
 
+/// Test case discovered in a test target sources
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+pub struct TestCaseInfo {
+    /// Test class
+    pub suite: String,
+    /// Test method, e.g. testExample
+    pub name: String,
+    /// Source file the test is declared in
+    pub file: PathBuf,
+    /// Line the test is declared at, starting at 1
+    pub line: u32,
+}
+
 impl From<PBXTargetInfo> for TargetInfo {
     fn from(info: PBXTargetInfo) -> Self {
         Self {
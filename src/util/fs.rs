@@ -16,7 +16,11 @@ pub fn get_dirname_dir_root(path: impl AsRef<Path>) -> Option<String> {
 }
 
 /// Ensure single socket server and process running
-pub async fn cleanup_daemon_runtime(pid_path: &'static str, sock_addr: &'static str) -> Result<()> {
+pub async fn cleanup_daemon_runtime(
+    pid_path: impl AsRef<Path>,
+    sock_addr: impl AsRef<Path>,
+) -> Result<()> {
+    let (pid_path, sock_addr) = (pid_path.as_ref(), sock_addr.as_ref());
     if fs::metadata(sock_addr).await.ok().is_some() {
         fs::remove_file(sock_addr).await.ok();
         if fs::metadata(pid_path).await.ok().is_some() {
@@ -5,5 +5,6 @@ pub mod extensions;
 pub mod fmt;
 pub mod fs;
 pub mod pid;
+pub mod token;
 pub mod tracing_setup;
 pub use extensions::*;
@@ -45,7 +45,10 @@ where
         }
     }
 
-    Err(crate::Error::Lookup("Process".into(), format!("{name}")))
+    Err(crate::Error::Lookup {
+        item: "Process".into(),
+        key: name.to_string(),
+    })
 }
 
 #[test]
@@ -1,3 +1,4 @@
+use once_cell::sync::OnceCell;
 use std::io;
 use std::path::Path;
 use tracing::dispatcher::SetGlobalDefaultError;
@@ -6,7 +7,26 @@ use tracing::Level;
 use tracing_appender::rolling;
 use tracing_subscriber::fmt::Layer;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
-use tracing_subscriber::{registry, EnvFilter};
+use tracing_subscriber::{registry, reload, EnvFilter, Registry};
+
+/// Handle to replace log filter after setup
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// Create filter with default level unless RUST_LOG is set
+fn filter(default_level: Level) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::from_default_env().add_directive(default_level.into()))
+        .add_directive("tokio_util=warn".parse().unwrap())
+}
+
+/// Change default log level
+pub fn set_level(default_level: Level) {
+    if let Some(handle) = FILTER.get() {
+        if let Err(err) = handle.reload(filter(default_level)) {
+            tracing::error!("Failed to change log level: {err}");
+        }
+    }
+}
 
 /// Setup tracing
 pub fn setup(
@@ -18,9 +38,8 @@ pub fn setup(
     let root = path.parent().unwrap();
     let filename = path.file_name().unwrap().to_str().unwrap();
 
-    let default_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::from_default_env().add_directive(default_level.into()))
-        .add_directive("tokio_util=warn".parse().unwrap());
+    let (default_filter, handle) = reload::Layer::new(filter(default_level));
+    FILTER.set(handle).ok();
 
     let fmt_file = Layer::new()
         .with_writer(rolling::never(root, filename))
@@ -145,6 +145,15 @@ impl WatchSubscribers {
         }
     }
 
+    /// Discard and remove all watch subscribers
+    #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
+    pub async fn clear(&mut self) {
+        for (key, w) in self.inner.drain() {
+            w.discard().await;
+            info!("Removed watch subscriber: `{key}`");
+        }
+    }
+
     pub fn keys(&self) -> Vec<String> {
         self.inner.keys().map(ToString::to_string).collect()
     }
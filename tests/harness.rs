@@ -0,0 +1,437 @@
+#![cfg(feature = "harness")]
+use serde_json::Value;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use xbase::client::Listener;
+use xbase::config::ConfigFile;
+use xbase::harness::{next_message, TestDaemon};
+use xbase::server::{
+    BuildRequest, DropRequest, RegisterRequest, Request, RequestKind, RunRequest, TasksRequest,
+    TestRequest,
+};
+use xbase::{BuildSettings, FakeProjectConfig, Operation, TargetInfo, TaskInfo};
+
+fn fake_project(name: &str, build: &str) -> FakeProjectConfig {
+    let target = TargetInfo {
+        platform: "macOS".into(),
+        configurations: vec!["Debug".into()],
+    };
+    FakeProjectConfig {
+        name: name.into(),
+        targets: HashMap::from([("App".to_string(), target)]),
+        build: build.into(),
+        ..Default::default()
+    }
+}
+
+fn settings(target: &str) -> BuildSettings {
+    BuildSettings {
+        target: target.into(),
+        configuration: "Debug".into(),
+        scheme: None,
+        sarif: None,
+        junit: None,
+    }
+}
+
+fn build_request(root: PathBuf) -> RequestKind {
+    RequestKind::Build(BuildRequest {
+        root,
+        settings: settings("App"),
+        operation: Operation::Once,
+    })
+}
+
+/// Wait for watch subscriptions persisted in daemon state for a given root to match a predicate
+async fn wait_for_state(
+    daemon: &TestDaemon,
+    root: &Path,
+    predicate: impl Fn(Option<&Value>) -> bool,
+) {
+    for _ in 0..100 {
+        let state = std::fs::read_to_string(&daemon.config.state_path).unwrap_or_default();
+        let state = serde_json::from_str::<Value>(&state).unwrap_or_default();
+        if predicate(state["projects"].get(root.to_str().unwrap())) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("Daemon state of {root:?} didn't match");
+}
+
+#[tokio::test]
+async fn test_register_build_broadcast() {
+    let daemon = TestDaemon::start().await.unwrap();
+
+    // Successful build
+    let root = daemon
+        .project(&fake_project(
+            "Success",
+            "echo Compiling App; echo Linking App",
+        ))
+        .unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+
+    client
+        .request::<()>(build_request(root.clone()))
+        .await
+        .unwrap();
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    let types = messages
+        .iter()
+        .map(|m| m["type"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    let contents = messages
+        .iter()
+        .filter_map(|m| m["args"]["content"].as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages[0]["args"]["kind"], "Build");
+    assert_eq!(messages[0]["args"]["target"], "App");
+    assert_eq!(
+        types,
+        [
+            "SetCurrentTask",
+            "UpdateCurrentTask",
+            "UpdateCurrentTask",
+            "FinishCurrentTask"
+        ]
+    );
+    assert_eq!(contents, ["Compiling App", "Linking App"]);
+    assert_eq!(messages[3]["args"]["status"], "Succeeded");
+    assert!(messages
+        .iter()
+        .all(|m| m["args"]["id"] == messages[0]["args"]["id"]));
+    assert!(messages.iter().all(|m| m["args"]["timestamp"].is_u64()));
+
+    let tasks: Vec<TaskInfo> = client
+        .request(RequestKind::Tasks(TasksRequest { root }))
+        .await
+        .unwrap();
+    assert!(tasks.is_empty());
+
+    // Failing build
+    let root = daemon
+        .project(&fake_project("Failure", "echo Compiling App; exit 1"))
+        .unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+
+    client
+        .request::<()>(build_request(root.clone()))
+        .await
+        .unwrap();
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Failed");
+
+    // Unknown project
+    let mut client = daemon.connect().await.unwrap();
+    let unknown = daemon.dir.join("unknown");
+    assert!(client.request::<()>(build_request(unknown)).await.is_err());
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_test_results() {
+    let daemon = TestDaemon::start().await.unwrap();
+    let tests = [
+        "Test Suite 'AppTests' started at 2022-10-18 10:00:00.000",
+        "Test Case 'AppTests.$XBASE_TEST_FILTER' started at 2022-10-18 10:00:00.001",
+        "Test Case 'AppTests.$XBASE_TEST_FILTER' passed (0.002 seconds)",
+        "Test Suite 'AppTests' passed at 2022-10-18 10:00:00.003",
+        "Executed 1 test, with 0 failures (0 unexpected) in 0.002 (0.002) seconds",
+    ];
+    let mut config = fake_project("Tested", "true");
+    config.test = Some(tests.map(|line| format!("echo \"{line}\"")).join("; "));
+    let root = daemon.project(&config).unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+
+    client
+        .request::<()>(RequestKind::Test(TestRequest {
+            root: root.clone(),
+            settings: settings("AppTests"),
+            filter: Some("testAdd".into()),
+            device: None,
+        }))
+        .await
+        .unwrap();
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    let cases = messages
+        .iter()
+        .filter(|m| m["type"] == "TestCase")
+        .map(|m| &m["args"]["case"])
+        .collect::<Vec<_>>();
+    let suites = messages
+        .iter()
+        .filter(|m| m["type"] == "TestSuite")
+        .map(|m| &m["args"]["suite"])
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages[0]["args"]["kind"], "Test");
+    assert_eq!(cases.len(), 2);
+    assert_eq!(cases[1]["name"], "testAdd");
+    assert_eq!(cases[1]["status"], "Passed");
+    assert_eq!(cases[1]["duration"], 2);
+    assert_eq!(suites.last().unwrap()["tests"], 1);
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Succeeded");
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_discover_tests() {
+    let daemon = TestDaemon::start().await.unwrap();
+    let mut config = fake_project("Discovered", "true");
+    let sources = PathBuf::from("Tests").join("AppTests");
+    config
+        .test_targets
+        .insert("AppTests".into(), sources.clone());
+    let root = daemon.project(&config).unwrap();
+    std::fs::create_dir_all(root.join(&sources)).unwrap();
+    std::fs::write(
+        root.join(&sources).join("AppTests.swift"),
+        "import XCTest\n\nclass AppTests: XCTestCase {\n    func testAdd() {}\n}\n",
+    )
+    .unwrap();
+
+    let mut client = daemon.connect().await.unwrap();
+    let response = client.register(root).await.unwrap();
+    let mut listener = Listener::connect(&response).await.unwrap();
+    let messages = next_message(&mut listener, |m| {
+        m["type"] == "SetState" && m["args"]["key"] == "projectInfo"
+    })
+    .await
+    .unwrap();
+    let tests = &messages.last().unwrap()["args"]["value"]["tests"]["AppTests"];
+    assert_eq!(tests[0]["suite"], "AppTests");
+    assert_eq!(tests[0]["name"], "testAdd");
+    assert_eq!(tests[0]["line"], 4);
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_broadcast_invalid_token() {
+    let daemon = TestDaemon::start().await.unwrap();
+    let root = daemon.project(&fake_project("Token", "true")).unwrap();
+
+    let mut response = daemon
+        .connect()
+        .await
+        .unwrap()
+        .register(root)
+        .await
+        .unwrap();
+    response.broadcast_token = "invalid".into();
+    let mut listener = Listener::connect(&response).await.unwrap();
+    assert_eq!(listener.next().await.unwrap()["kind"], "Unauthorized");
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_tcp_token() {
+    let file = ConfigFile {
+        tcp_addr: Some("127.0.0.1:0".into()),
+        tcp_token: Some("secret".into()),
+        ..Default::default()
+    };
+    let daemon = TestDaemon::start_with(file).await.unwrap();
+    let root = daemon.project(&fake_project("Remote", "true")).unwrap();
+
+    for (token, authorized) in [
+        (None, false),
+        (Some("invalid"), false),
+        (Some("secret"), true),
+    ] {
+        let request = Request {
+            id: Some(1),
+            kind: RequestKind::Register(RegisterRequest {
+                id: 0,
+                root: root.clone(),
+                client: None,
+                token: token.map(Into::into),
+                session: None,
+            }),
+        };
+        let stream = TcpStream::connect(daemon.tcp_addr.unwrap()).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let line = format!("{}\n", serde_json::to_string(&request).unwrap());
+        writer.write_all(line.as_bytes()).await.unwrap();
+
+        let mut lines = BufReader::new(reader).lines();
+        let line = lines.next_line().await.unwrap().unwrap();
+        let response = serde_json::from_str::<Value>(&line).unwrap();
+        assert_eq!(response["id"], 1);
+        if authorized {
+            assert!(response["error"].is_null());
+            assert!(response["data"]["broadcast_token"].is_string());
+        } else {
+            assert_eq!(response["error"]["kind"], "Unauthorized");
+            assert!(lines.next_line().await.unwrap().is_none());
+        }
+    }
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_persisted_watchers() {
+    let daemon = TestDaemon::start().await.unwrap();
+    let root = daemon
+        .project(&fake_project("Watched", "echo Compiling App"))
+        .unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+
+    let mut request = build_request(root.clone());
+    if let RequestKind::Build(ref mut build) = request {
+        build.operation = Operation::Watch;
+    }
+    client.request::<()>(request).await.unwrap();
+    next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    let watchers = |state: Option<&Value>| state.and_then(|w| w.as_array()).map(Vec::len);
+    wait_for_state(&daemon, &root, |state| watchers(state) == Some(1)).await;
+
+    // Watchers are restored once the project is registered after a restart
+    xbase::shutdown_runtimes().await;
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+    next_message(&mut listener, |m| {
+        m["args"]["content"]
+            .as_str()
+            .map_or(false, |c| c.contains("Restoring 1 watcher(s)"))
+    })
+    .await
+    .unwrap();
+    wait_for_state(&daemon, &root, |state| watchers(state) == Some(1)).await;
+
+    // Root is forgotten once its last client disconnects
+    let drop = DropRequest {
+        id: 0,
+        roots: vec![root.clone()],
+    };
+    client.request::<()>(RequestKind::Drop(drop)).await.unwrap();
+    wait_for_state(&daemon, &root, |state| state.is_none()).await;
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_cancel_tasks() {
+    let daemon = TestDaemon::start().await.unwrap();
+
+    // Runner stopped by running it again
+    let mut config = fake_project("Runner", "true");
+    config.run = Some("run.sh".into());
+    let root = daemon.project(&config).unwrap();
+    let run = root.join("run.sh");
+    std::fs::write(&run, "#!/bin/sh\nsleep 30\n").unwrap();
+    std::fs::set_permissions(&run, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+
+    let request = RunRequest {
+        root: root.clone(),
+        settings: settings("App"),
+        device: None,
+        operation: Operation::Once,
+    };
+    client
+        .request::<()>(RequestKind::Run(request.clone()))
+        .await
+        .unwrap();
+    let messages = next_message(&mut listener, |m| {
+        m["type"] == "SetCurrentTask" && m["args"]["kind"] == "Run"
+    })
+    .await
+    .unwrap();
+    let id = messages.last().unwrap()["args"]["id"].clone();
+    client
+        .request::<()>(RequestKind::Run(request))
+        .await
+        .unwrap();
+    let messages = next_message(&mut listener, |m| {
+        m["type"] == "FinishCurrentTask" && m["args"]["id"] == id
+    })
+    .await
+    .unwrap();
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Canceled");
+
+    // Build running while the daemon shuts down
+    let root = daemon
+        .project(&fake_project("Slow", "echo Compiling App; sleep 30"))
+        .unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+    client
+        .request::<()>(build_request(root.clone()))
+        .await
+        .unwrap();
+    next_message(&mut listener, |m| m["args"]["content"] == "Compiling App")
+        .await
+        .unwrap();
+    xbase::shutdown_runtimes().await;
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Canceled");
+
+    daemon.stop().await;
+}
+
+#[tokio::test]
+async fn test_stop_and_shutdown_busy_runtime() {
+    let daemon = TestDaemon::start().await.unwrap();
+    let mut config = fake_project("Busy", "echo Compiling App; sleep 30");
+    config.run = Some("run.sh".into());
+    let root = daemon.project(&config).unwrap();
+    let (mut client, mut listener) = daemon.register(&root).await.unwrap();
+    let build = |operation| {
+        RequestKind::Build(BuildRequest {
+            root: root.clone(),
+            settings: settings("App"),
+            operation,
+        })
+    };
+
+    // Stopping a watched build aborts the build under progress
+    client.request::<()>(build(Operation::Watch)).await.unwrap();
+    next_message(&mut listener, |m| m["args"]["content"] == "Compiling App")
+        .await
+        .unwrap();
+    client.request::<()>(build(Operation::Stop)).await.unwrap();
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Canceled");
+
+    // Shutdown doesn't wait for the runtime to finish processing a runner waiting on its build
+    let run = RunRequest {
+        root: root.clone(),
+        settings: settings("App"),
+        device: None,
+        operation: Operation::Once,
+    };
+    client.request::<()>(RequestKind::Run(run)).await.unwrap();
+    next_message(&mut listener, |m| m["args"]["content"] == "Compiling App")
+        .await
+        .unwrap();
+    let started = std::time::Instant::now();
+    xbase::shutdown_runtimes().await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let messages = next_message(&mut listener, |m| m["type"] == "FinishCurrentTask")
+        .await
+        .unwrap();
+    assert_eq!(messages.last().unwrap()["args"]["status"], "Canceled");
+
+    daemon.stop().await;
+}
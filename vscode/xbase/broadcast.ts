@@ -1,7 +1,7 @@
 import { filter, map, pipe, split, toAsync } from "iter-ops";
 import net from "net";
 import { Disposable, window, commands } from "vscode";
-import { Message, ContentLevel, TaskKind, TaskStatus } from "./types";
+import { Message, ContentLevel, TaskKind, TaskStatus, TestCase, TestSuite } from "./types";
 import Logger from "./ui/logger";
 import Statusline from "./ui/statusline";
 import configuration from "./config";
@@ -10,10 +10,13 @@ import SourcekitLsp from "./sourcekit-lsp";
 import FolderContext from "./folderContext";
 
 interface CurrentTask {
+  id: number,
   prefix: { processing: string, done: string },
   target: string,
   kind: TaskKind,
   status: TaskStatus,
+  /** Estimated progress, e.g. " 42%" */
+  progress?: string,
 }
 
 export default class Broadcast implements Disposable {
@@ -22,7 +25,10 @@ export default class Broadcast implements Disposable {
   public socket: net.Socket;
   private logger: Logger;
   private statusline: Statusline;
-  private currentTask?: CurrentTask;
+  /** Tasks under progress indexed by task id */
+  private tasks = new Map<number, CurrentTask>();
+  /** Id of the task shown in the statusline */
+  private shownTask?: number;
   private sourcekit: SourcekitLsp;
 
   private constructor(
@@ -39,6 +45,8 @@ export default class Broadcast implements Disposable {
   public static async connect(
     folder: FolderContext,
     address: string,
+    id: number,
+    token: string,
     ctx: WorkspaceContext
   ): Promise<Broadcast> {
     return new Promise((resolve, reject) => {
@@ -48,7 +56,7 @@ export default class Broadcast implements Disposable {
           for await (const message of Broadcast.get_messages(buffer))
             await broadcast.handleMessage(message);
         });
-        socket.write(`${process.pid}\n`);
+        socket.write(`${id} ${token}\n`);
         resolve(broadcast);
       });
       socket.on("error", err => {
@@ -86,13 +94,25 @@ export default class Broadcast implements Disposable {
           this.logger.toggle();
         break;
       case "SetCurrentTask":
-        this.setTask(message.args.kind, message.args.target, message.args.status);
+        this.setTask(message.args.id, message.args.kind, message.args.target, message.args.status);
         break;
       case "UpdateCurrentTask":
-        this.updateCurrentTask(message.args.content, message.args.level);
+        this.updateTask(message.args.id, message.args.content, message.args.level);
+        break;
+      case "TaskProgress":
+        this.progressTask(message.args.id, message.args.completed, message.args.total);
+        break;
+      case "Diagnostic":
+        this.logger.addDiagnostic(message.args.diagnostic);
+        break;
+      case "TestSuite":
+        this.testSuite(message.args.suite);
+        break;
+      case "TestCase":
+        this.testCase(message.args.case);
         break;
       case "FinishCurrentTask":
-        await this.finishTask(message.args.status);
+        await this.finishTask(message.args.id, message.args.status);
         break;
       case "ReloadLspServer":
         await this.sourcekit.restartClient(this.folderCtx.uri);
@@ -112,17 +132,21 @@ export default class Broadcast implements Disposable {
     }
   }
 
-  private setTask(kind: TaskKind, target: string, status: TaskStatus) {
+  private setTask(id: number, kind: TaskKind, target: string, status: TaskStatus) {
     const prefix = TaskKind.prefix(kind)!;
-    this.currentTask = { target, kind, status, prefix };
+    this.tasks.set(id, { id, target, kind, status, prefix });
+    if (TaskKind.isBuild(kind))
+      this.logger.clearDiagnostics();
+    this.shownTask = id;
     this.statusline.update({
       content: `[${target}] ${prefix.processing}`,
       icon: TaskKind.isRun(kind) ? "$(code)" : undefined
     });
   }
 
-  private updateCurrentTask(content: string, level: ContentLevel) {
-    if (this.currentTask === undefined) {
+  private updateTask(id: number, content: string, level: ContentLevel) {
+    const task = this.tasks.get(id);
+    if (task === undefined) {
       console.warn("trying to update task that no longer exists!");
       return;
     };
@@ -130,28 +154,68 @@ export default class Broadcast implements Disposable {
     if (levelShouldIgnore(level))
       return;
 
-    const { target, prefix, kind } = this.currentTask;
+    const { target, prefix, kind, progress = "" } = task;
 
     this.logger.append(content, level);
 
-    content = content.replace(`[${this.currentTask.target}]`, "");
+    // Show the task unless another task under progress is already shown
+    if (this.shownTask === undefined || !this.tasks.has(this.shownTask))
+      this.shownTask = id;
+    if (this.shownTask !== id)
+      return;
+
+    content = content.replace(`[${target}]`, "");
 
     this.statusline.update({
-      content: `[${target}] ${prefix.processing}: ${content}`,
+      content: `[${target}] ${prefix.processing}${progress}: ${content}`,
       icon: TaskKind.isRun(kind) ? "$(code)" : undefined,
       level
     });
   }
 
-  private async finishTask(status: TaskStatus) {
-    if (this.currentTask === undefined) {
+  private progressTask(id: number, completed: number, total: number) {
+    const task = this.tasks.get(id);
+    if (task === undefined || total === 0)
+      return;
+
+    task.progress = ` ${Math.floor(completed * 100 / total)}%`;
+    if (this.shownTask !== id)
+      return;
+
+    this.statusline.update({
+      content: `[${task.target}] ${task.prefix.processing}${task.progress}`,
+      icon: TaskKind.isRun(task.kind) ? "$(code)" : undefined
+    });
+  }
+
+  private testSuite(suite: TestSuite) {
+    if (suite.status === "Started")
+      return;
+    const level = suite.status === "Failed" ? "Error" : "Info";
+    this.logger.append(`[${suite.name}] ${suite.status}: ${suite.tests} tests, ${suite.failures} failures`, level);
+  }
+
+  private testCase(testCase: TestCase) {
+    const { suite, name, status, duration, failures } = testCase;
+    if (status === "Started")
+      return;
+    const level = status === "Failed" ? "Error" : "Info";
+    this.logger.append(`  ${status} ${suite}.${name} (${duration ?? 0}ms)`, level);
+    failures.forEach(failure =>
+      this.logger.append(`    ${failure.file}:${failure.line}: ${failure.message}`, "Error"));
+  }
+
+  private async finishTask(id: number, status: TaskStatus) {
+    const task = this.tasks.get(id);
+    if (task === undefined) {
       console.warn("trying to finish task that no longer exists!");
       return;
     }
 
-    const { target, prefix, kind } = { ...this.currentTask };
-    const taskFailed = (status === "Failed");
-    this.currentTask = undefined;
+    const { target, prefix, kind } = task;
+    const taskFailed = (status === "Failed" || status === "Canceled");
+    const shown = this.shownTask === id;
+    this.tasks.delete(id);
 
     const level = taskFailed ? "Error" : "Info";
     const content = TaskKind.isRun(kind)
@@ -162,6 +226,9 @@ export default class Broadcast implements Disposable {
 
     this.logger.append(content, level);
 
+    if (!shown)
+      return;
+
     this.statusline.set({
       icon: taskFailed ? "$(error)" : "$(pass)",
       content,
@@ -172,7 +239,8 @@ export default class Broadcast implements Disposable {
 
     if (!taskFailed) {
       await (new Promise(resolve => setTimeout(resolve, 3000)));
-      this.statusline.setDefault();
+      if (this.tasks.size === 0)
+        this.statusline.setDefault();
     }
   }
 
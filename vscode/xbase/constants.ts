@@ -2,5 +2,58 @@
 //
 // ----
 // AUTOGENERATED
-export const XBASE_SOCK_ADDR = '/tmp/xbase.socket'
+export const XBASE_CONFIG_PATH_ENV = 'XBASE_CONFIG'
+export const XBASE_CONFIG_FILE = 'xbase/daemon.json'
+export const XBASE_RUNTIME_DIR_ENV = 'XBASE_RUNTIME_DIR'
+export const XBASE_SOCK_ADDR_ENV = 'XBASE_SOCK_ADDR'
+export const XBASE_LOG_PATH_ENV = 'XBASE_LOG_PATH'
+export const XBASE_SOCK_NAME = 'xbase.socket'
+export const XBASE_LOG_NAME = 'xbase.log'
+export const XBASE_PROTOCOL_VERSION = 2
+export const XBASE_ERROR_CODES = {
+  Unexpected: 1,
+  Setup: 2,
+  Lookup: 3,
+  Build: 4,
+  Run: 5,
+  Generate: 6,
+  Compile: 7,
+  DefinitionParsing: 8,
+  DefinitionLocating: 9,
+  DefinitionMutliFound: 10,
+  JoinError: 11,
+  SendError: 12,
+  MessageParse: 13,
+  RequestParse: 14,
+  Incompatible: 15,
+  Unauthorized: 16,
+  UnknownProject: 17,
+} as const
 export const XBASE_BIN_ROOT = '$HOME/.local/share/xbase'.replace('$HOME', process.env.HOME!)
+
+import * as fs from "fs";
+import * as os from "os";
+
+const env = (key: string) => {
+  const value = process.env[key];
+  return value !== undefined && value.length > 0 ? value : undefined;
+};
+
+const configFile = (): Record<string, string | undefined> => {
+  const home = env("XDG_CONFIG_HOME") ?? `${process.env.HOME}/.config`;
+  const path = env(XBASE_CONFIG_PATH_ENV) ?? `${home}/${XBASE_CONFIG_FILE}`;
+  try {
+    return JSON.parse(fs.readFileSync(path, "utf8"));
+  } catch {
+    return {};
+  }
+};
+
+const config = configFile();
+const xdgRuntimeDir = env("XDG_RUNTIME_DIR");
+
+export const XBASE_RUNTIME_DIR = env(XBASE_RUNTIME_DIR_ENV)
+  ?? config.runtime_dir
+  ?? (xdgRuntimeDir !== undefined ? `${xdgRuntimeDir}/xbase` : `${os.tmpdir()}/xbase-${process.getuid?.()}`);
+export const XBASE_SOCK_ADDR = env(XBASE_SOCK_ADDR_ENV) ?? config.sock_addr ?? `${XBASE_RUNTIME_DIR}/${XBASE_SOCK_NAME}`;
+export const XBASE_LOG_PATH = env(XBASE_LOG_PATH_ENV) ?? config.log_path ?? `${XBASE_RUNTIME_DIR}/${XBASE_LOG_NAME}`;
@@ -30,7 +30,8 @@ export default class FolderContext implements Disposable {
 
     folderCtx.subscriptions.push(
       await ctx.server.register(uri.fsPath)
-        .then(address => Broadcast.connect(folderCtx, address, ctx))
+        .then(({ address, id, broadcast_token }) =>
+          Broadcast.connect(folderCtx, address, id, broadcast_token, ctx))
         .catch(error => {
           throw Error(`[${name}] Failed to Initialize: ${error}`);
         }));
@@ -38,6 +39,11 @@ export default class FolderContext implements Disposable {
     ctx.statusline.setDefault();
     console.log(`[${name}] Registered`);
 
+    // Show problems of builds that finished before the project was opened
+    await ctx.server.getDiagnostics(uri.fsPath)
+      .then(targets => Object.values(targets).flat().forEach(d => ctx.logger.addDiagnostic(d)))
+      .catch(error => console.warn(`[${name}] Failed to get diagnostics: ${error}`));
+
     return folderCtx;
   }
 
@@ -1,11 +1,38 @@
 import net from "net";
-import type { JSONValue, Request, Response } from "./types";
+import type { Diagnostic, JSONValue, RegisterResponse, Request, Response, TestRequest } from "./types";
 import { Disposable } from "vscode";
 import { spawn } from "child_process";
-import { XBASE_BIN_ROOT } from "./constants";
+import { XBASE_BIN_ROOT, XBASE_PROTOCOL_VERSION, XBASE_SOCK_ADDR } from "./constants";
+
+/**
+ * Message kinds handled by this client
+ */
+const SUPPORTED_MESSAGES = [
+  "Notify",
+  "Log",
+  "OpenLogger",
+  "ReloadLspServer",
+  "SetCurrentTask",
+  "UpdateCurrentTask",
+  "TaskProgress",
+  "Diagnostic",
+  "TestSuite",
+  "TestCase",
+  "FinishCurrentTask",
+  "SetState",
+];
+
+type PendingRequest = {
+  resolve: (value: JSONValue | undefined) => void;
+  reject: (reason: Error) => void;
+};
 
 export default class Server implements Disposable {
   roots: string[] = [];
+  private nextId = 0;
+  private session?: string;
+  private buffer = "";
+  private pending = new Map<number, PendingRequest>();
 
   private static onConnect = (resolve: (value: Server) => void, socket: net.Socket) =>
     () => {
@@ -13,11 +40,13 @@ export default class Server implements Disposable {
       resolve(new Server(socket));
     };
   ;
-  private constructor(public socket: net.Socket) { }
+  private constructor(public socket: net.Socket) {
+    socket.on("data", (chunk) => this.onData(chunk));
+  }
 
   public static async connect(): Promise<Server> {
     return new Promise((resolve, reject) => {
-      const socket = net.createConnection("/tmp/xbase.socket");
+      const socket = net.createConnection(XBASE_SOCK_ADDR);
 
       socket.on("error", () => {
         console.log("[XBase] No socket running, spawning");
@@ -27,7 +56,7 @@ export default class Server implements Disposable {
         // The timeout is needed to give some time for xbase to startup
         // NOTE: child.on('spawn') doesn't cut it.
         setTimeout(() => {
-          const socket = net.createConnection("/tmp/xbase.socket");
+          const socket = net.createConnection(XBASE_SOCK_ADDR);
           socket.on("connect", Server.onConnect(resolve, socket));
           socket.on("error", (err) => reject(Error(`Failed to connect to xbase socket: ${err}`)));
         }, 500);
@@ -38,49 +67,87 @@ export default class Server implements Disposable {
   }
 
   // Register a given root
-  async register(root: string): Promise<string> {
-    const value = await this.request({ method: "register", args: { root, id: process.pid } })
+  async register(root: string): Promise<RegisterResponse> {
+    const client = { name: "vscode", protocol: XBASE_PROTOCOL_VERSION, messages: SUPPORTED_MESSAGES };
+    const value = await this.request({ method: "register", args: { root, client, session: this.session } })
       .catch(error => {
         throw Error(`Registeration failed: ${error}`);
       });
 
-    if (typeof value === "string") return value;
+    const response = (value ?? {}) as Partial<RegisterResponse>;
+    if (typeof response.address === "string" && typeof response.id === "number") {
+      this.session = response.session;
+      return response as RegisterResponse;
+    }
 
-    throw Error(`Expected response to be a string, got ${value}`);
+    throw Error(`Expected response to have an address, got ${value}`);
   }
 
   // Drop a root project
   async drop(root: string): Promise<void> {
-    await this.request({ method: "drop", args: { id: process.pid, roots: [root] } })
+    await this.request({ method: "drop", args: { roots: [root] } })
       .catch(error => {
         throw Error(`Drop failed: ${error}`);
       });
   }
 
+  // Get diagnostics of the last build of each target of a root project
+  async getDiagnostics(root: string, file?: string): Promise<Record<string, Diagnostic[]>> {
+    const value = await this.request({ method: "get_diagnostics", args: { root, file } });
+    return (value ?? {}) as Record<string, Diagnostic[]>;
+  }
+
+  // Build and run tests of a test target, results are broadcast as TestSuite and TestCase messages
+  async test(args: TestRequest): Promise<void> {
+    await this.request({ method: "test", args })
+      .catch(error => {
+        throw Error(`Test failed: ${error}`);
+      });
+  }
+
   /**
     * Send a new request to xbase server
   */
   public async request(req: Request): Promise<JSONValue | undefined> {
     const { socket } = this;
-    const data = JSON.stringify(req);
+    const id = this.nextId++;
+    const data = JSON.stringify({ ...req, id });
 
     return new Promise((resolve, reject) => {
+      this.pending.set(id, { resolve, reject });
       socket.write(`${data}\n`, (error) => {
         if (error !== undefined) {
+          this.pending.delete(id);
           return reject(new Error);
-        } else {
-          socket.once("data", (buffer) => {
-            const { error, data } = JSON.parse(`${buffer}`) as Response;
-            if (error)
-              reject(new Error(`Server Errored: (${error.kind}): ${error.msg}`));
-            else
-              resolve(data);
-          });
         }
       });
     });
   }
 
+  /**
+    * Process newline delimited responses and resolve their pending requests
+  */
+  private onData(chunk: Buffer) {
+    this.buffer += `${chunk}`;
+    const lines = this.buffer.split("\n");
+    this.buffer = lines.pop() ?? "";
+
+    for (const line of lines) {
+      if (line.trim().length === 0) continue;
+      const { id, error, data } = JSON.parse(line) as Response;
+      const pending = id !== undefined ? this.pending.get(id) : undefined;
+      if (pending === undefined) {
+        console.error(`[XBase] Received response for unknown request: ${line}`);
+        continue;
+      }
+      this.pending.delete(id!);
+      if (error)
+        pending.reject(new Error(`Server Errored: (${error.kind}): ${error.msg}`));
+      else
+        pending.resolve(data);
+    }
+  }
+
   dispose() {
     this.socket.pause();
     this.socket.end();
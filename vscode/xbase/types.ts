@@ -7,6 +7,8 @@ export namespace TaskKind {
       return { processing: "Building", done: "Built", value: "Build" };
     } else if (isCompile(kind)) {
       return { processing: "Compiling", done: "Compiled", value: "Compile" };
+    } else if (isTest(kind)) {
+      return { processing: "Testing", done: "Tested", value: "Test" };
     } else if (isGenerate(kind)) {
       return {
         processing: "Generating",
@@ -19,6 +21,7 @@ export namespace TaskKind {
   export const isBuild = (kind: TaskKind) => kind === "Build";
   export const isGenerate = (kind: TaskKind) => kind === "Generate";
   export const isCompile = (kind: TaskKind) => kind === "Compile";
+  export const isTest = (kind: TaskKind) => kind === "Test";
 }
 
 // AUTOGENERATED
@@ -67,7 +70,11 @@ export type TaskKind =
   /**
    * Generate xcodeproj
    */
-  | "Generate";
+  | "Generate"
+  /**
+   * Test Task
+   */
+  | "Test";
 
 /**
  * What the status of task is currently under progress?
@@ -84,7 +91,145 @@ export type TaskStatus =
   /**
    * Processing Task,
    */
-  | "Processing";
+  | "Processing"
+  /**
+   * Task Canceled, e.g. on shutdown
+   */
+  | "Canceled";
+export type U64 = number;
+
+/**
+ * Severity of a compiler diagnostic
+ */
+export type DiagnosticSeverity = "Note" | "Warning" | "Error";
+
+/**
+ * Note attached to a compiler diagnostic
+ */
+export type DiagnosticNote =
+  /**
+   * Note attached to a compiler diagnostic
+   */
+  { file: string; line: U32; column: U32; message: string };
+
+/**
+ * Compiler diagnostic parsed from build output
+ */
+export type Diagnostic =
+  /**
+   * Compiler diagnostic parsed from build output
+   */
+  {
+    /**
+     * Source file the diagnostic is about
+     */
+    file: string;
+    /**
+     * Line number, starting at 1
+     */
+    line: U32;
+    /**
+     * Column number, starting at 1, 0 if unknown
+     */
+    column: U32;
+    severity: DiagnosticSeverity;
+    message: string;
+    /**
+     * Notes following the diagnostic, e.g. where a conflicting symbol is declared
+     */
+    notes: DiagnosticNote[];
+  };
+
+/**
+ * Status of a test case or suite
+ */
+export type TestStatus = "Started" | "Passed" | "Failed" | "Skipped";
+
+/**
+ * Failed assertion of a test case
+ */
+export type TestFailure =
+  /**
+   * Failed assertion of a test case
+   */
+  { file: string; line: U32; message: string };
+
+/**
+ * Test case started or finished
+ */
+export type TestCase =
+  /**
+   * Test case started or finished
+   */
+  {
+    /**
+     * Test class, without module name
+     */
+    suite: string;
+    /**
+     * Test method, e.g. testExample
+     */
+    name: string;
+    status: TestStatus;
+    /**
+     * Duration in milliseconds, once finished
+     */
+    duration: U64 | null;
+    /**
+     * Failed assertions of the test case
+     */
+    failures: TestFailure[];
+  };
+
+/**
+ * Test suite started or finished, e.g. a test class or bundle
+ */
+export type TestSuite =
+  /**
+   * Test suite started or finished, e.g. a test class or bundle
+   */
+  {
+    name: string;
+    status: TestStatus;
+    /**
+     * Number of test cases executed, once finished
+     */
+    tests: U32;
+    /**
+     * Number of test cases failed, once finished
+     */
+    failures: U32;
+    /**
+     * Duration in milliseconds, once finished
+     */
+    duration: U64 | null;
+  };
+
+/**
+ * Information about a task currently under progress
+ */
+export type TaskInfo =
+  /**
+   * Information about a task currently under progress
+   */
+  {
+    /**
+     * Task id, unique within the daemon lifetime
+     */
+    id: U32;
+    /**
+     * Task kind
+     */
+    kind: TaskKind;
+    /**
+     * Target the task is processing
+     */
+    target: string;
+    /**
+     * When the task started, in milliseconds since unix epoch
+     */
+    started: U64;
+  };
 
 /**
  * Build Settings used in building/running a target/scheme
@@ -106,6 +251,14 @@ export type BuildSettings =
      * Scheme to build with
      */
     scheme: string | null;
+    /**
+     * Path to write a SARIF report of build diagnostics to
+     */
+    sarif?: string;
+    /**
+     * Path to write a JUnit report of the build outcome to
+     */
+    junit?: string;
   };
 
 /**
@@ -139,6 +292,32 @@ export type ProjectInfo = {
    * Get targets information for a registers project with a given root
    */
   targets: Record<string, TargetInfo>;
+  /**
+   * Test targets with the test cases discovered in their sources
+   */
+  tests: Record<string, TestCaseInfo[]>;
+};
+
+/**
+ * Test case discovered in a test target sources
+ */
+export type TestCaseInfo = {
+  /**
+   * Test class
+   */
+  suite: string;
+  /**
+   * Test method, e.g. testExample
+   */
+  name: string;
+  /**
+   * Source file the test is declared in
+   */
+  file: string;
+  /**
+   * Line the test is declared at, starting at 1
+   */
+  line: number;
 };
 
 /**
@@ -174,19 +353,106 @@ export type Message =
     }
   | {
       /**
-       * Set Current Task
+       * Start a task, tasks may overlap and are told apart by their id
        */
       type: "SetCurrentTask";
-      args: { kind: TaskKind; target: string; status: TaskStatus };
+      args: {
+        id: U32;
+        kind: TaskKind;
+        target: string;
+        status: TaskStatus;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
     }
   | {
       /**
-       * Update Current Task
+       * Update a task with a new line of output
        */
       type: "UpdateCurrentTask";
-      args: { content: string; level: ContentLevel };
+      args: {
+        id: U32;
+        content: string;
+        level: ContentLevel;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
+    }
+  | {
+      /**
+       * Estimated progress of a task, sent as its steps complete
+       */
+      type: "TaskProgress";
+      args: {
+        id: U32;
+        completed: U64;
+        total: U64;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
+    }
+  | {
+      /**
+       * Compiler diagnostic parsed from a task output
+       */
+      type: "Diagnostic";
+      args: {
+        id: U32;
+        diagnostic: Diagnostic;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
+    }
+  | {
+      /**
+       * Test suite of a test task started or finished
+       */
+      type: "TestSuite";
+      args: {
+        id: U32;
+        suite: TestSuite;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
+    }
+  | {
+      /**
+       * Test case of a test task started or finished
+       */
+      type: "TestCase";
+      args: {
+        id: U32;
+        case: TestCase;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
+    }
+  | {
+      /**
+       * Finish a task
+       */
+      type: "FinishCurrentTask";
+      args: {
+        id: U32;
+        status: TaskStatus;
+        /**
+         * Milliseconds since unix epoch
+         */
+        timestamp: U64;
+      };
     }
-  | { type: "FinishCurrentTask"; args: { status: TaskStatus } }
   | {
       /**
        * Notify client that something is being watched
@@ -214,14 +480,122 @@ export type JSONValue =
   | JSONValue[]
   | { [key: string]: JSONValue };
 
+export type I32 = number;
+export type Error =
+  | {
+      kind: "Setup";
+      data: { project: string; msg: string };
+    }
+  | {
+      kind: "Lookup";
+      data: { item: string; key: string };
+    }
+  | {
+      kind: "Build";
+      data: {
+        target: string;
+        msg: string;
+        /**
+         * Exit code of the failed build process
+         */
+        exit_code: I32 | null;
+        /**
+         * Last lines of the failed build process stderr
+         */
+        stderr: string[];
+      };
+    }
+  | {
+      kind: "Run";
+      data: {
+        /**
+         * Target that failed to run, if known
+         */
+        target: string | null;
+        msg: string;
+        /**
+         * Exit code of the failed process
+         */
+        exit_code: I32 | null;
+        /**
+         * Last lines of the failed process stderr
+         */
+        stderr: string[];
+      };
+    }
+  | {
+      kind: "Generate";
+    }
+  | {
+      kind: "Compile";
+    }
+  | {
+      kind: "DefinitionParsing";
+      data: string;
+    }
+  | {
+      kind: "DefinitionLocating";
+    }
+  | {
+      kind: "DefinitionMutliFound";
+    }
+  | {
+      kind: "Unexpected";
+      data: string;
+    }
+  | {
+      kind: "JoinError";
+      data: string;
+    }
+  | {
+      kind: "SendError";
+      data: string;
+    }
+  | {
+      kind: "MessageParse";
+      data: string;
+    }
+  | {
+      kind: "RequestParse";
+      data: string;
+    }
+  | {
+      kind: "Incompatible";
+      data: string;
+    }
+  | {
+      kind: "Unauthorized";
+      data: string;
+    }
+  | {
+      kind: "UnknownProject";
+      data: string;
+    };
+export type U16 = number;
+
 /**
- * Server Error due to failure while processing a `Request
+ * Server Error due to failure while processing a `Request`
+ *
+ * Serialized as `{ code, msg, kind, data }`, where `kind` and `data` are the [`Error`] variant
+ * and its fields.
  */
 export type ServerError =
   /**
-   * Server Error due to failure while processing a `Request
+   * Server Error due to failure while processing a `Request`
+   *
+   * Serialized as `{ code, msg, kind, data }`, where `kind` and `data` are the [`Error`] variant
+   * and its fields.
    */
-  { kind: string; msg: string };
+  {
+    /**
+     * Stable error code, see [`ERROR_CODES`]
+     */
+    code: U16;
+    /**
+     * Human readable message
+     */
+    msg: string;
+  } & Error;
 
 /**
  * Server Response
@@ -230,9 +604,38 @@ export type Response =
   /**
    * Server Response
    */
-  { data?: JSONValue; error?: ServerError };
+  {
+    /**
+     * Id of the request this response is for, if the request had one
+     */
+    id?: U32;
+    data?: JSONValue;
+    error?: ServerError;
+  };
 export type U32 = number;
 
+/**
+ * Client information sent on registration
+ */
+export type ClientInfo =
+  /**
+   * Client information sent on registration
+   */
+  {
+    /**
+     * Client name, e.g. nvim or vscode
+     */
+    name: string;
+    /**
+     * Protocol version the client speaks
+     */
+    protocol: U32;
+    /**
+     * Message kinds the client is able to process
+     */
+    messages: string[];
+  };
+
 /**
  * Register a project root
  */
@@ -240,7 +643,82 @@ export type RegisterRequest =
   /**
    * Register a project root
    */
-  { id: U32; root: string };
+  {
+    /**
+     * Client id, assigned by the daemon and ignored if sent by the client
+     */
+    id?: U32;
+    root: string;
+    /**
+     * Client information used to ensure client/daemon compatibility
+     */
+    client?: ClientInfo | null;
+    /**
+     * Shared secret required from clients connected over tcp
+     */
+    token?: string | null;
+    /**
+     * Session token returned by a previous registration, used to resume the session
+     * after reconnecting
+     */
+    session?: string | null;
+  };
+
+/**
+ * Daemon information returned on registration
+ */
+export type DaemonInfo =
+  /**
+   * Daemon information returned on registration
+   */
+  {
+    /**
+     * Daemon version
+     */
+    version: string;
+    /**
+     * Protocol version the daemon speaks
+     */
+    protocol: U32;
+    /**
+     * Request methods the daemon can process
+     */
+    methods: string[];
+    /**
+     * Message kinds the daemon broadcast
+     */
+    messages: string[];
+  };
+
+/**
+ * Registration result
+ */
+export type RegisterResponse =
+  /**
+   * Registration result
+   */
+  {
+    /**
+     * Client id assigned by the daemon, to be sent to the broadcaster
+     */
+    id: U32;
+    /**
+     * Session token to resume the session after reconnecting
+     */
+    session: string;
+    /**
+     * Broadcaster address to read messages from
+     */
+    address: string;
+    /**
+     * Daemon information
+     */
+    /**
+     * Token to send to the broadcaster after the client id
+     */
+    broadcast_token: string;
+    daemon: DaemonInfo;
+  };
 
 /**
  * Request to build a particular project
@@ -272,12 +750,124 @@ export type DropRequest =
   /**
    * Drop a given set of roots to be dropped (i.e. unregistered)
    */
-  { id: U32; roots: string[] };
+  {
+    /**
+     * Client id, assigned by the daemon and ignored if sent by the client
+     */
+    id?: U32;
+    roots: string[];
+  };
+
+/**
+ * Get diagnostics of the last build of each target of a registered project
+ */
+export type GetDiagnosticsRequest =
+  /**
+   * Get diagnostics of the last build of each target of a registered project
+   */
+  {
+    root: string;
+    /**
+     * Only get diagnostics about a given file, relative to root or absolute
+     */
+    file?: string | null;
+  };
+
+/**
+ * Get tasks under progress of a registered project, oldest first
+ */
+export type TasksRequest =
+  /**
+   * Get tasks under progress of a registered project, oldest first
+   */
+  { root: string };
+
+/**
+ * Request to build and run tests of a test target
+ */
+export type TestRequest =
+  /**
+   * Request to build and run tests of a test target
+   */
+  {
+    root: string;
+    /**
+     * Settings with the test target to build and run
+     */
+    settings: BuildSettings;
+    /**
+     * Only run tests matching `Class` or `Class/testName`
+     */
+    filter?: string | null;
+    /**
+     * Simulator to run tests on, macOS if none
+     */
+    device?: DeviceLookup | null;
+  };
+
+/**
+ * Broadcast messages a client wants to receive.
+ *
+ * Sent as a json line over the broadcast socket after the client id, and again at any time to
+ * replace it. Filters left empty accept everything.
+ */
+export type Subscription =
+  /**
+   * Broadcast messages a client wants to receive.
+   *
+   * Sent as a json line over the broadcast socket after the client id, and again at any time to
+   * replace it. Filters left empty accept everything.
+   */
+  {
+    /**
+     * Minimum level of Notify, Log and UpdateCurrentTask messages
+     */
+    level: ContentLevel | null;
+    /**
+     * Message types to receive, e.g. Notify
+     */
+    messages: string[];
+    /**
+     * Task kinds to receive SetCurrentTask, UpdateCurrentTask, TaskProgress and FinishCurrentTask
+     * messages of
+     */
+    tasks: TaskKind[];
+  };
+
+/**
+ * Turn the connection into a broadcast listener of a registered project.
+ *
+ * For clients that can't reach broadcast unix socket, e.g. remote clients connected over tcp.
+ * Once the request succeeds, no more requests are read and only broadcast messages are written.
+ */
+export type AttachRequest =
+  /**
+   * Turn the connection into a broadcast listener of a registered project.
+   *
+   * For clients that can't reach broadcast unix socket, e.g. remote clients connected over tcp.
+   * Once the request succeeds, no more requests are read and only broadcast messages are written.
+   */
+  {
+    id: U32;
+    root: string;
+    /**
+     * Shared secret required from clients connected over tcp
+     */
+    token?: string | null;
+    /**
+     * Token returned by the register request
+     */
+    broadcast_token?: string;
+    /**
+     * Broadcast messages to receive
+     */
+    subscription?: Subscription;
+  };
 
 /**
  * Requests clinets can make
  */
-export type Request =
+export type RequestKind =
   | {
       /**
        * Register project root and get broadcaster reader file description
@@ -305,4 +895,46 @@ export type Request =
        */
       method: "drop";
       args: DropRequest;
+    }
+  | {
+      /**
+       * Receive project broadcast messages through the current connection
+       */
+      method: "attach";
+      args: AttachRequest;
+    }
+  | {
+      /**
+       * Get tasks under progress of a registered project
+       */
+      method: "tasks";
+      args: TasksRequest;
+    }
+  | {
+      /**
+       * Get diagnostics of the last build of each target of a registered project
+       */
+      method: "get_diagnostics";
+      args: GetDiagnosticsRequest;
+    }
+  | {
+      /**
+       * Build and run tests of a test target, streaming test suites and cases results
+       */
+      method: "test";
+      args: TestRequest;
     };
+
+/**
+ * Request clients can make, with an optional id echoed back in the response
+ */
+export type Request =
+  /**
+   * Request clients can make, with an optional id echoed back in the response
+   */
+  {
+    /**
+     * Client supplied id used to match a response to its request
+     */
+    id?: U32 | null;
+  } & RequestKind;
@@ -1,5 +1,5 @@
 import vscode, { DiagnosticSeverity, Position, Range, Uri } from "vscode";
-import { ContentLevel } from "../types";
+import { ContentLevel, Diagnostic } from "../types";
 
 
 
@@ -25,46 +25,48 @@ export default class Logger implements vscode.Disposable {
   }
 
   append(line: string, level: ContentLevel = "Debug") {
-    const groups = this.problemRegex.exec(line);
-
-    if (groups) {
-      const filePath = Uri.file(groups[2]);
-      const lineNr = parseInt(groups[3]);
-      const colNr = parseInt(groups[4]);
-      const type = groups[5] ? groups[5] : groups[1].toLowerCase();
-      const message = groups[6];
-      const currentDiagnostics = this.diagnosticCollection.get(filePath);
-      const diagnostics = [...(currentDiagnostics ? currentDiagnostics : [])];
+    this.channel.appendLine(line);
+    switch (level) {
+      case "Error": console.error(line); break;
+      case "Warn": console.warn(line); break;
+      case "Debug": console.debug(line); break;
+      case "Info": console.info(line); break;
+    }
+  }
 
-      let severity;
-      if (type === "error")
-        severity = DiagnosticSeverity.Error;
-      else if (type === "warn" || type === "warning")
-        severity = DiagnosticSeverity.Warning;
-      else
-        severity = DiagnosticSeverity.Information;
+  /* show a compiler diagnostic in problems panel */
+  addDiagnostic(diagnostic: Diagnostic) {
+    const filePath = Uri.file(diagnostic.file);
+    const diagnostics = [...(this.diagnosticCollection.get(filePath) ?? [])];
+    const position = (line: number, column: number) =>
+      new Position(Math.max(line - 1, 0), Math.max(column - 1, 0));
+    const start = position(diagnostic.line, diagnostic.column);
 
-      // TODO: Ensure that this message doesn't already exists in problems!
-      diagnostics.push({
-        message,
-        range: new Range(
-          new Position(lineNr, colNr),
-          new Position(lineNr, colNr + 10)
-        ),
-        severity,
-      });
+    const item = new vscode.Diagnostic(
+      new Range(start, start.translate(0, 10)),
+      diagnostic.message,
+      {
+        Error: DiagnosticSeverity.Error,
+        Warning: DiagnosticSeverity.Warning,
+        Note: DiagnosticSeverity.Information,
+      }[diagnostic.severity]
+    );
+    item.source = "xbase";
+    item.relatedInformation = diagnostic.notes.map(note => {
+      const location = position(note.line, note.column);
+      return new vscode.DiagnosticRelatedInformation(
+        new vscode.Location(Uri.file(note.file), location),
+        note.message
+      );
+    });
 
-      this.diagnosticCollection.set(filePath, diagnostics);
+    diagnostics.push(item);
+    this.diagnosticCollection.set(filePath, diagnostics);
+  }
 
-    } else if (/\[(Error|Warning)\]/.test(line) === false) {
-      this.channel.appendLine(line);
-      switch (level) {
-        case "Error": console.error(line); break;
-        case "Warn": console.warn(line); break;
-        case "Debug": console.debug(line); break;
-        case "Info": console.info(line); break;
-      }
-    }
+  /* clear problems reported by previous builds */
+  clearDiagnostics() {
+    this.diagnosticCollection.clear();
   }
 
   dispose() {